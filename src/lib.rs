@@ -5,15 +5,306 @@
 pub mod tree {
     use std::collections::TreeMap;
     use std::str::eq_slice;
+    use std::fmt;
 
 
+    #[doc = "Number of training examples of each class that reached a vertex."]
+    pub type ClassCounts = TreeMap<String, uint>;
+
+    #[doc = "Trait for measures of how mixed a class distribution is."]
+    pub trait Impurity {
+        /// Impurity of a distribution given its class counts, zero when only one class is present.
+        fn impurity(&self, class_counts: &[f64]) -> f64;
+    }
+
+    #[doc = "Impurity measures a tree can be grown with."]
+    #[deriving(Clone, PartialEq, Show)]
+    pub enum ImpurityMeasure {
+        /// Entropy in bits, `-sum(p * log2(p))`.
+        Entropy,
+        /// Gini impurity, `1 - sum(p^2)`.
+        Gini,
+        /// Misclassification error of the majority class, `1 - max(p)`.
+        ClassificationError,
+    }
+
+    impl Impurity for ImpurityMeasure {
+        fn impurity(&self, class_counts: &[f64]) -> f64 {
+            let total = class_counts.iter().fold(0f64, |sum, x| sum + *x);
+            if total == 0f64 { return 0f64; }
+            let proportions = class_counts.iter().map(|x| *x / total);
+
+            match *self {
+                Entropy => -proportions.filter(|p| *p > 0f64).fold(0f64, |sum, p| sum + p * p.log2()),
+                Gini => 1f64 - proportions.fold(0f64, |sum, p| sum + p * p),
+                ClassificationError => 1f64 - proportions.fold(0f64, |max, p| if p > max { p } else { max }),
+            }
+        }
+    }
+
+    #[doc = "Summary of the training examples that reached a vertex."]
+    #[deriving(Clone, PartialEq, Show)]
+    pub struct NodeStats {
+        /// Number of training examples that reached the vertex.
+        pub samples: uint,
+        /// Number of those examples in each class.
+        pub class_counts: ClassCounts,
+        /// Entropy of the class distribution, in bits.
+        pub entropy: f64,
+        /// Impurity measure the tree was grown with.
+        pub measure: ImpurityMeasure,
+        /// Impurity of the class distribution under `measure`.
+        pub impurity: f64,
+    }
+
+    impl NodeStats {
+        pub fn new(class_counts: ClassCounts) -> NodeStats {
+            NodeStats::with_measure(class_counts, Entropy)
+        }
+
+        pub fn with_measure(class_counts: ClassCounts, measure: ImpurityMeasure) -> NodeStats {
+            let samples = class_counts.values().fold(0u, |sum, x| sum + *x);
+            let counts: Vec<f64> = class_counts.values().map(|x| *x as f64).collect();
+
+            NodeStats {
+                samples: samples,
+                entropy: Entropy.impurity(counts.as_slice()),
+                impurity: measure.impurity(counts.as_slice()),
+                measure: measure,
+                class_counts: class_counts,
+            }
+        }
+
+        #[doc = "Fraction of the vertex's training examples belonging to the given class."]
+        pub fn proportion(&self, class: &str) -> f64 {
+            if self.samples == 0 { return 0f64; }
+            match self.class_counts.find(&class.to_string()) {
+                Some(count) => *count as f64 / self.samples as f64,
+                None => 0f64,
+            }
+        }
+    }
+
+    #[doc = "Where a split sends records whose attribute holds the missing-value marker."]
+    #[deriving(Clone, PartialEq, Show)]
+    pub enum MissingRoute {
+        /// Down every child, weighted by the share of training examples each received (C4.5).
+        DistributeMissing,
+        /// Down the child that received the most training examples.
+        LargestChild,
+    }
+
+    #[doc = "How a split treats records missing the attribute it tests."]
+    #[deriving(Clone, PartialEq, Show)]
+    pub struct MissingSplit {
+        /// The missing-value marker, e.g. `?`.
+        pub marker: String,
+        /// Where records carrying the marker are sent.
+        pub route: MissingRoute,
+    }
+
     #[doc = "A vertex in a decision tree."]
-    #[deriving(Show)]
+    #[deriving(Clone)]
     pub enum DecisionVertex {
-        /// Vertex at which the example set is split along an attribute.
-        Branch(String, TreeMap<String,DecisionVertex>),
+        /// Vertex at which the example set is split along an attribute. Records missing the attribute
+        /// are routed by the `MissingSplit`, if there is one, and otherwise looked up like any value.
+        Branch(String, TreeMap<String,DecisionVertex>, Option<MissingSplit>, NodeStats),
+        /// Vertex at which the example set is split by comparing a numeric attribute to a threshold.
+        /// The first child takes values `<=` the threshold, the second values `>` it.
+        Threshold(String, f64, Box<DecisionVertex>, Box<DecisionVertex>, Option<MissingSplit>, NodeStats),
         /// Terminal vertex whose class has been decided.
-        Leaf(String),
+        Leaf(String, NodeStats),
+    }
+
+    impl DecisionVertex {
+        #[doc = "Statistics of the training examples that reached this vertex."]
+        pub fn stats(&self) -> &NodeStats {
+            match *self {
+                Branch(_, _, _, ref stats) => stats,
+                Threshold(_, _, _, _, _, ref stats) => stats,
+                Leaf(_, ref stats) => stats,
+            }
+        }
+
+        #[doc = "Child vertices in order, none for a leaf."]
+        pub fn children(&self) -> Vec<&DecisionVertex> {
+            match *self {
+                Branch(_, ref children, _, _) => children.values().collect(),
+                Threshold(_, _, ref below, ref above, _, _) => vec![&**below, &**above],
+                Leaf(..) => vec![],
+            }
+        }
+
+        #[doc = "Mutable child vertices, in the same order as `children`."]
+        pub fn children_mut(&mut self) -> Vec<&mut DecisionVertex> {
+            match *self {
+                Branch(_, ref mut children, _, _) => children.iter_mut().map(|(_, child)| child).collect(),
+                Threshold(_, _, ref mut below, ref mut above, _, _) => vec![&mut **below, &mut **above],
+                Leaf(..) => vec![],
+            }
+        }
+
+        #[doc = "Number of vertices in the tree rooted here."]
+        pub fn node_count(&self) -> uint {
+            self.children().iter().fold(1u, |sum, x| sum + x.node_count())
+        }
+
+        #[doc = "Length of the longest path from here down to a leaf, 0 for a leaf."]
+        pub fn depth(&self) -> uint {
+            self.children().iter().map(|x| x.depth() + 1).max().unwrap_or(0)
+        }
+
+        #[doc = "Number of leaves in the tree rooted here."]
+        pub fn leaf_count(&self) -> uint {
+            match *self {
+                Leaf(..) => 1u,
+                _ => self.children().iter().fold(0u, |sum, x| sum + x.leaf_count()),
+            }
+        }
+
+        // Where the record goes if it holds this vertex's missing-value marker
+        fn missing_route<T: Record>(&self, record: &T) -> Option<MissingRoute> {
+            let (attr, missing) = match *self {
+                Branch(ref attr, _, ref missing, _) => (attr, missing),
+                Threshold(ref attr, _, _, _, ref missing, _) => (attr, missing),
+                Leaf(..) => return None,
+            };
+            match *missing {
+                Some(ref missing) if eq_slice(record.get_attribute(attr.as_slice()), missing.marker.as_slice()) => Some(missing.route),
+                _ => None,
+            }
+        }
+
+        #[doc = "
+        Position in `children` of the child a record is sent to.
+
+        Returns `None` for leaves, for records with a value no child was trained on, and for
+        records whose missing value is distributed over every child.
+        "]
+        pub fn child_index<T: Record>(&self, record: &T) -> Option<uint> {
+            match self.missing_route(record) {
+                Some(LargestChild) => {
+                    let mut largest: Option<(uint, uint)> = None;
+                    for (index, child) in self.children().iter().enumerate() {
+                        let samples = child.stats().samples;
+                        if largest.map_or(true, |(_, most)| samples > most) {
+                            largest = Some((index, samples));
+                        }
+                    }
+                    return largest.map(|(index, _)| index);
+                },
+                Some(DistributeMissing) => return None,
+                None => {},
+            }
+
+            match *self {
+                Branch(ref attr, ref children, _, _) => {
+                    let attr_value = record.get_attribute(attr.as_slice());
+                    children.keys().position(|x| eq_slice(x.as_slice(), attr_value))
+                },
+                Threshold(ref attr, threshold, _, _, _, _) => {
+                    match from_str::<f64>(record.get_attribute(attr.as_slice()).trim()).and_then(|x| if x.is_finite() { Some(x) } else { None }) {
+                        Some(value) if value <= threshold => Some(0),
+                        Some(_) => Some(1),
+                        None => None,
+                    }
+                },
+                Leaf(..) => None,
+            }
+        }
+
+        #[doc = "Impurity measure the tree was grown with."]
+        pub fn measure(&self) -> ImpurityMeasure {
+            self.stats().measure
+        }
+
+        // The child a record is sent to, if there is exactly one
+        fn child_for<T: Record>(&self, record: &T) -> Option<&DecisionVertex> {
+            match self.child_index(record) {
+                Some(index) => Some(self.children()[index]),
+                None => None,
+            }
+        }
+
+        fn fmt_indented(&self, f: &mut fmt::Formatter, depth: uint) -> fmt::Result {
+            match *self {
+                Leaf(ref label, ref stats) => {
+                    try!(write!(f, "{} ", label));
+                    fmt_stats(f, stats)
+                },
+                Branch(ref attr, ref children, _, ref stats) => {
+                    try!(write!(f, "{}? ", attr));
+                    try!(fmt_stats(f, stats));
+                    for (attr_value, child) in children.iter() {
+                        try!(fmt_newline(f, depth + 1));
+                        try!(write!(f, "{} = {}: ", attr, attr_value));
+                        try!(child.fmt_indented(f, depth + 1));
+                    }
+                    Ok(())
+                },
+                Threshold(ref attr, threshold, ref below, ref above, _, ref stats) => {
+                    try!(write!(f, "{}? ", attr));
+                    try!(fmt_stats(f, stats));
+                    try!(fmt_newline(f, depth + 1));
+                    try!(write!(f, "{} <= {}: ", attr, threshold));
+                    try!(below.fmt_indented(f, depth + 1));
+                    try!(fmt_newline(f, depth + 1));
+                    try!(write!(f, "{} > {}: ", attr, threshold));
+                    above.fmt_indented(f, depth + 1)
+                },
+            }
+        }
+    }
+
+    fn fmt_newline(f: &mut fmt::Formatter, depth: uint) -> fmt::Result {
+        try!(write!(f, "\n"));
+        for _ in range(0, depth) {
+            try!(write!(f, "  "));
+        }
+        Ok(())
+    }
+
+    // Writes e.g. "[5 samples, entropy 0.722, n: 1, y: 4]", naming any other impurity measure too
+    fn fmt_stats(f: &mut fmt::Formatter, stats: &NodeStats) -> fmt::Result {
+        try!(write!(f, "[{} samples, entropy {:.3}", stats.samples, stats.entropy));
+        match stats.measure {
+            Entropy => {},
+            Gini => try!(write!(f, ", gini {:.3}", stats.impurity)),
+            ClassificationError => try!(write!(f, ", error {:.3}", stats.impurity)),
+        }
+        for (class, count) in stats.class_counts.iter() {
+            try!(write!(f, ", {}: {}", class, count));
+        }
+        write!(f, "]")
+    }
+
+    impl fmt::Show for DecisionVertex {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            self.fmt_indented(f, 0)
+        }
+    }
+
+    #[doc = "What a prediction does when a record's value has no child at a branch."]
+    #[deriving(Clone, PartialEq, Show)]
+    pub enum UnseenValue {
+        /// Predict the majority class of the training examples that reached the branch.
+        BranchMajority,
+        /// Send the record down every child and combine their class distributions, each weighted
+        /// by the share of the branch's training examples that reached that child (C4.5 style).
+        Distribute,
+        /// Predict nothing.
+        Unknown,
+    }
+
+    #[doc = "Smoothing applied to the class counts behind a probability estimate."]
+    #[deriving(Clone, PartialEq, Show)]
+    pub enum Smoothing {
+        /// Relative class frequencies, unsmoothed.
+        NoSmoothing,
+        /// Add one to every class count, i.e. `(n_c + 1) / (n + k)` for `k` classes.
+        Laplace,
+        /// m-estimate `(n_c + m * p_c) / (n + m)`, where `p_c` is the class's proportion at the root.
+        MEstimate(f64),
     }
     
     #[doc = "Trait for labeled data."]
@@ -23,176 +314,4147 @@ pub mod tree {
         fn get_label(&self) -> &str;
     }    
 
-    pub fn test<T: Record>(tree_vertex: &DecisionVertex, test_case: &T, label_attribute_name: &str) -> bool {
-        match *tree_vertex {
-            Leaf(ref label) => eq_slice(label.as_slice(), test_case.get_attribute(label_attribute_name)),
-            Branch(ref label, ref children) => {
-                let test_attr: String = test_case.get_attribute(label.as_slice()).to_string();
-                let next_vertex: &DecisionVertex = match children.find(&test_attr) {
-                    Some(val) => val,
-                    None => return false,
-                };
-                test(next_vertex, test_case, label_attribute_name)
+    #[cfg(test)]
+    #[doc = "Record made of (attribute, value) pairs whose label is the `class` attribute. Used in tests."]
+    pub struct TestRecord {
+        pub pairs: Vec<(&'static str, &'static str)>,
+    }
+
+    #[cfg(test)]
+    pub fn record(pairs: Vec<(&'static str, &'static str)>) -> TestRecord {
+        TestRecord { pairs: pairs }
+    }
+
+    #[cfg(test)]
+    impl Record for TestRecord {
+        fn get_attribute(&self, attr_name: &str) -> &str {
+            match self.pairs.iter().find(|&&(name, _)| eq_slice(name, attr_name)) {
+                Some(&(_, value)) => value,
+                None => fail!("no attribute named {}", attr_name),
             }
         }
+
+        fn get_attribute_names(&self) -> Vec<&str> {
+            self.pairs.iter().map(|&(name, _)| name).filter(|x| !eq_slice(*x, "class")).collect()
+        }
+
+        fn get_label(&self) -> &str {
+            self.get_attribute("class")
+        }
     }
-}
 
-#[experimental]
-#[doc = "ID3 decision tree implementation"]
-pub mod id3 {
-    use std::collections::HashSet;
-    use std::collections::TreeMap;
-    use std::str::eq_slice;
-    use std::f64;
-    use tree::{Record, DecisionVertex, Leaf, Branch};
+    #[doc = "
+    Predicts the class of a record by walking the tree down from the given vertex.
+
+    Returns `None` if the record reaches a branch with no child for its attribute value, or if
+    the tree was grown from no examples. Equivalent to `predict_with(tree_vertex, record, Unknown)`.
+    "]
+    pub fn predict<T: Record>(tree_vertex: &DecisionVertex, record: &T) -> Option<String> {
+        predict_with(tree_vertex, record, Unknown)
+    }
 
-    #[unstable]
     #[doc = "
-    ID3 Decision Tree Algorithm
+    Predicts the class of a record by walking the tree down from the given vertex.
 
-    Recursively generates a decision tree from a dataset. [Wikipedia Link](http://en.wikipedia.org/wiki/ID3_algorithm)
+    Records missing the tested attribute are routed as the split's `MissingSplit` says. Other records
+    with an attribute value no branch child was trained on, or with a numeric attribute value that
+    does not parse, are handled according to `unseen`. A tree grown from no examples predicts
+    `None`.
+    "]
+    pub fn predict_with<T: Record>(tree_vertex: &DecisionVertex, record: &T, unseen: UnseenValue) -> Option<String> {
+        match *tree_vertex {
+            // Only an empty dataset leaves a leaf with no examples and no parent to label it
+            Leaf(_, ref stats) if stats.samples == 0 => None,
+            Leaf(ref label, _) => Some(label.clone()),
+            _ => match tree_vertex.child_for(record) {
+                // Partitions no example reached carry their parent's majority label
+                Some(&Leaf(ref label, ref stats)) if stats.samples == 0 => Some(label.clone()),
+                Some(next_vertex) => predict_with(next_vertex, record, unseen),
+                None if tree_vertex.missing_route(record) == Some(DistributeMissing) => {
+                    distributed_counts(tree_vertex, record, unseen).and_then(|x| most_probable(&x))
+                },
+                None => match unseen {
+                    BranchMajority => most_probable(&float_counts(tree_vertex.stats())),
+                    Distribute => reached_counts(tree_vertex, record, unseen).and_then(|x| most_probable(&x)),
+                    Unknown => None,
+                },
+            },
+        }
+    }
 
-    # Arguments
+    #[doc = "Predicts the class of each record in turn. See `predict`."]
+    pub fn predict_all<T: Record>(tree_vertex: &DecisionVertex, records: &[T]) -> Vec<Option<String>> {
+        records.iter().map(|x| predict(tree_vertex, x)).collect()
+    }
 
-    * 'dataset' - The dataset to label or recursively split.
-    * 'label_attribute_name' - The name of the classification attribute.
+    #[doc = "Predicts the class of each record in turn. See `predict_with`."]
+    pub fn predict_all_with<T: Record>(tree_vertex: &DecisionVertex, records: &[T], unseen: UnseenValue) -> Vec<Option<String>> {
+        records.iter().map(|x| predict_with(tree_vertex, x, unseen)).collect()
+    }
 
-    # Safety Note
+    #[doc = "
+    Estimates the probability of each class for a record.
 
-    This is, in its present form, an incomplete and relatively untested implementation. It may fail unexpectedly. Do not use in a production setting.
+    The estimate comes from the class counts of the training examples at the leaf the record
+    reaches, smoothed according to `smoothing`. Every class seen at `tree_vertex`, normally the
+    root, is given a probability. Leaves no training example reached borrow their parent's counts.
+    Returns `None` if `unseen` is `Unknown` and the record has an unseen attribute value, if
+    no training examples back the estimate and `smoothing` is `NoSmoothing`, or if the tree was
+    grown from no examples.
     "]
-    pub fn id3<T: Record>(dataset: Vec<&T>, label_attribute_name: &str, attributes: Vec<&str>, entropy_threshold: f64) -> DecisionVertex {
+    pub fn predict_proba<T: Record>(tree_vertex: &DecisionVertex, record: &T, unseen: UnseenValue, smoothing: Smoothing) -> Option<TreeMap<String, f64>> {
+        if tree_vertex.stats().samples == 0 {
+            return None;
+        }
+        let counts = match reached_counts(tree_vertex, record, unseen) {
+            Some(counts) => counts,
+            None => return None,
+        };
+        let total = counts.values().fold(0f64, |sum, x| sum + *x);
+        if total == 0f64 && smoothing == NoSmoothing {
+            return None;
+        }
 
-        // Return labeled leaf if all labels in dataset are equal
-        if attr_all_eq(&dataset, label_attribute_name) {
-            return Leaf(dataset.iter().next().unwrap().get_attribute(label_attribute_name).to_string());
+        let root_stats = tree_vertex.stats();
+        let class_total = root_stats.class_counts.len() as f64;
+        let mut probabilities = TreeMap::new();
+        for class in root_stats.class_counts.keys() {
+            let count = match counts.find(class) {
+                Some(count) => *count,
+                None => 0f64,
+            };
+            let probability = match smoothing {
+                NoSmoothing => count / total,
+                Laplace => (count + 1f64) / (total + class_total),
+                MEstimate(m) => (count + m * root_stats.proportion(class.as_slice())) / (total + m),
+            };
+            probabilities.insert(class.clone(), probability);
         }
 
-        // Choose attribute to split on
-        // Assumes split_attribute() is Some
-        let split_attr_name = split_attribute(&dataset, label_attribute_name, &attributes).unwrap();
+        Some(probabilities)
+    }
 
-        let mut branch_map = TreeMap::new();
-        // Add a new branch for each possible attribute value
-        let attr_domain: HashSet<&str> = dataset.iter().map(|x| x.get_attribute(split_attr_name.as_slice())).collect();
-        for attr_value in attr_domain.iter() {
-            let example_subset: Vec<&T> = dataset.iter().filter(|x| eq_slice(x.get_attribute(split_attr_name.as_slice()), *attr_value)).map(|x| *x).collect();
-            let attrs_left = attributes.iter().filter(|x| !eq_slice(**x, split_attr_name.as_slice())).map(|x| *x).collect();
-            let child_vertex = id3(example_subset, label_attribute_name, attrs_left, entropy_threshold);
-            branch_map.insert(attr_value.to_string(), child_vertex);
+    // Class counts of the training examples at the leaf (or leaves) a record reaches. Counts
+    // combined across several children are scaled to the size of the branch they were combined at.
+    fn reached_counts<T: Record>(tree_vertex: &DecisionVertex, record: &T, unseen: UnseenValue) -> Option<TreeMap<String, f64>> {
+        let stats = tree_vertex.stats();
+        match *tree_vertex {
+            Leaf(..) => return Some(float_counts(stats)),
+            _ => {},
+        }
+        match tree_vertex.child_for(record) {
+            // Partitions no example reached were labeled from this branch's examples
+            Some(next_vertex) if next_vertex.stats().samples == 0 => return Some(float_counts(stats)),
+            Some(next_vertex) => return reached_counts(next_vertex, record, unseen),
+            None => {},
         }
 
-        Branch(split_attr_name.to_string(), branch_map)
+        if tree_vertex.missing_route(record) == Some(DistributeMissing) {
+            return distributed_counts(tree_vertex, record, unseen);
+        }
+        match unseen {
+            BranchMajority => Some(float_counts(stats)),
+            Distribute => distributed_counts(tree_vertex, record, unseen),
+            Unknown => None,
+        }
     }
 
-    // Checks the given attribute for equality among all records
-    fn attr_all_eq<T: Record>(records: &Vec<&T>, attr_name: &str) -> bool {
-        let first_attr = records[0].get_attribute(attr_name);
-        let all_eq = records.iter().map(|x| x.get_attribute(attr_name)).all(|x| eq_slice(first_attr,x));
+    // Counts reached by sending the record down every child of the vertex, scaled to its size
+    fn distributed_counts<T: Record>(tree_vertex: &DecisionVertex, record: &T, unseen: UnseenValue) -> Option<TreeMap<String, f64>> {
+        let stats = tree_vertex.stats();
+        let mut combined = TreeMap::new();
+        for child in tree_vertex.children().into_iter() {
+            let weight = child.stats().samples as f64 / stats.samples as f64;
+            if weight == 0f64 { continue; }
+            let child_counts = match reached_counts(child, record, unseen) {
+                Some(counts) => counts,
+                None => continue,
+            };
+            let child_total = child_counts.values().fold(0f64, |sum, x| sum + *x);
+            for (class, count) in child_counts.iter() {
+                let sum = match combined.find(class) {
+                    Some(sum) => *sum,
+                    None => 0f64,
+                };
+                combined.insert(class.clone(), sum + weight * stats.samples as f64 * *count / child_total);
+            }
+        }
 
-        all_eq
+        if combined.is_empty() { None } else { Some(combined) }
     }
 
-    // assumes records.iter().next() is Some
-    fn split_attribute<T: Record>(records: &Vec<&T>, label_attr_name: &str, attributes: &Vec<&str>) -> Option<String> {
-        let mut min_entropy = f64::MAX_VALUE;
-        let mut best_attr = None;
+    fn float_counts(stats: &NodeStats) -> TreeMap<String, f64> {
+        stats.class_counts.iter().map(|(class, count)| (class.clone(), *count as f64)).collect()
+    }
 
-        for attr_name in attributes.iter() {
-            // Assuming discrete attributes
-            if eq_slice(*attr_name, label_attr_name) { continue; }
-            let attr_label_pairs: Vec<(&str, &str)> = records.iter().map(|x| (x.get_attribute(*attr_name),x.get_attribute(label_attr_name))).collect();
-            let entropy = split_entropy(attr_label_pairs);
-            if entropy < min_entropy {
-                best_attr = Some(attr_name.to_string());
-                min_entropy = entropy;
+    // Class with the highest count, ties going to the lexicographically smallest class
+    fn most_probable(counts: &TreeMap<String, f64>) -> Option<String> {
+        let mut best_class = None;
+        let mut best_count = 0f64;
+        for (class, count) in counts.iter() {
+            if best_class.is_none() || *count > best_count {
+                best_class = Some(class.clone());
+                best_count = *count;
             }
         }
 
-        best_attr
+        best_class
     }
 
-    fn split_entropy(attributes: Vec<(&str,&str)>) -> f64 {
-        let mut total_entropy = 0f64;
-        // N_m
-        let total_attr_count = attributes.iter().count() as f64;
+    #[doc = "Checks whether the tree's prediction for a record matches the label it already carries."]
+    pub fn test<T: Record>(tree_vertex: &DecisionVertex, test_case: &T, label_attribute_name: &str) -> bool {
+        match predict(tree_vertex, test_case) {
+            Some(label) => eq_slice(label.as_slice(), test_case.get_attribute(label_attribute_name)),
+            None => false,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn counts(pairs: Vec<(&'static str, uint)>) -> NodeStats {
+        NodeStats::new(pairs.iter().map(|&(class, count)| (class.to_string(), count)).collect())
+    }
+
+    #[cfg(test)]
+    fn weather_tree() -> DecisionVertex {
+        let mut children = TreeMap::new();
+        children.insert("sunny".to_string(), Leaf("n".to_string(), counts(vec![("n", 3)])));
+        children.insert("rain".to_string(), Leaf("y".to_string(), counts(vec![("n", 1), ("y", 4)])));
+        Branch("outlook".to_string(), children, None, counts(vec![("n", 4), ("y", 4)]))
+    }
+
+    #[test]
+    fn predict_follows_branch() {
+        let tree = weather_tree();
+
+        assert_eq!(Some("n".to_string()), predict(&tree, &record(vec![("outlook", "sunny")])));
+        assert_eq!(Some("y".to_string()), predict(&tree, &record(vec![("outlook", "rain")])));
+    }
+
+    #[test]
+    fn tree_size() {
+        let tree = weather_tree();
+
+        assert_eq!((3, 2, 1), (tree.node_count(), tree.leaf_count(), tree.depth()));
+        assert_eq!((1, 1, 0), (tree.children()[0].node_count(), tree.children()[0].leaf_count(), tree.children()[0].depth()));
+    }
+
+    #[test]
+    fn predict_unseen_value() {
+        let tree = weather_tree();
+        let overcast = record(vec![("outlook", "overcast")]);
+
+        assert_eq!(None, predict(&tree, &overcast));
+        assert_eq!(None, predict_with(&tree, &overcast, Unknown));
+        // Branch saw 4 n and 4 y, so the tie goes to n
+        assert_eq!(Some("n".to_string()), predict_with(&tree, &overcast, BranchMajority));
+    }
+
+    #[test]
+    fn predict_unseen_value_distributed() {
+        let mut wind_children = TreeMap::new();
+        wind_children.insert("strong".to_string(), Leaf("n".to_string(), counts(vec![("n", 2)])));
+        wind_children.insert("weak".to_string(), Leaf("y".to_string(), counts(vec![("y", 2)])));
+        let mut children = TreeMap::new();
+        children.insert("sunny".to_string(), Leaf("y".to_string(), counts(vec![("y", 3)])));
+        children.insert("rain".to_string(), Branch("wind".to_string(), wind_children, None, counts(vec![("n", 2), ("y", 2)])));
+        let tree = Branch("outlook".to_string(), children, None, counts(vec![("n", 2), ("y", 5)]));
+        let overcast = record(vec![("outlook", "overcast"), ("wind", "strong")]);
+
+        // 3/7 * {y: 1} + 4/7 * {n: 1}, where the branch alone favors y
+        assert_eq!(Some("n".to_string()), predict_with(&tree, &overcast, Distribute));
+        assert_eq!(Some("y".to_string()), predict_with(&tree, &overcast, BranchMajority));
+    }
+
+    #[test]
+    fn predict_all_and_test() {
+        let tree = weather_tree();
+        let records = vec![
+            record(vec![("outlook", "sunny"), ("class", "n")]),
+            record(vec![("outlook", "overcast"), ("class", "y")])];
+
+        assert_eq!(vec![Some("n".to_string()), None], predict_all(&tree, records.as_slice()));
+        assert_eq!(vec![Some("n".to_string()), Some("n".to_string())], predict_all_with(&tree, records.as_slice(), BranchMajority));
+        assert!(test(&tree, &records[0], "class"));
+        assert!(!test(&tree, &records[1], "class"));
+    }
+
+    #[test]
+    fn predict_threshold() {
+        let below = Leaf("y".to_string(), counts(vec![("y", 3)]));
+        let above = Leaf("n".to_string(), counts(vec![("n", 2)]));
+        let tree = Threshold("x".to_string(), 6.5f64, box below, box above, None, counts(vec![("n", 2), ("y", 3)]));
+
+        assert_eq!(Some("y".to_string()), predict(&tree, &record(vec![("x", "6.5")])));
+        assert_eq!(Some("n".to_string()), predict(&tree, &record(vec![("x", "20")])));
+        assert_eq!(None, predict(&tree, &record(vec![("x", "?")])));
+        assert_eq!(Some("y".to_string()), predict_with(&tree, &record(vec![("x", "?")]), BranchMajority));
+        assert_eq!("x? [5 samples, entropy 0.971, n: 2, y: 3]\n  x <= 6.5: y [3 samples, entropy 0.000, y: 3]\n  x > 6.5: n [2 samples, entropy 0.000, n: 2]".to_string(),
+                   format!("{}", tree));
+    }
+
+    #[test]
+    fn predict_missing_value_routes() {
+        let missing = |route| Some(MissingSplit { marker: "?".to_string(), route: route });
+        let children = || {
+            let mut children = TreeMap::new();
+            children.insert("n".to_string(), Leaf("y".to_string(), counts(vec![("y", 3)])));
+            children.insert("y".to_string(), Leaf("n".to_string(), counts(vec![("n", 4), ("y", 1)])));
+            children
+        };
+        let distributed = Branch("vote".to_string(), children(), missing(DistributeMissing), counts(vec![("n", 4), ("y", 4)]));
+        let largest = Branch("vote".to_string(), children(), missing(LargestChild), counts(vec![("n", 4), ("y", 4)]));
+        let absent = record(vec![("vote", "?")]);
+
+        // 3/8 * {y: 1} + 5/8 * {n: 0.8, y: 0.2} = {n: 0.5, y: 0.5}
+        let proba = predict_proba(&distributed, &absent, Unknown, NoSmoothing).unwrap();
+        assert_eq!(Some(&0.5f64), proba.find(&"n".to_string()));
+        assert_eq!(Some("n".to_string()), predict(&largest, &absent));
+        assert_eq!(None, predict(&largest, &record(vec![("vote", "abstain")])));
+    }
+
+    #[test]
+    fn predict_proba_smoothing() {
+        let tree = weather_tree();
+        let rain = record(vec![("outlook", "rain")]);
+
+        let raw = predict_proba(&tree, &rain, Unknown, NoSmoothing).unwrap();
+        assert_eq!(Some(&0.8f64), raw.find(&"y".to_string()));
+        // (4 + 1) / (5 + 2)
+        let laplace = predict_proba(&tree, &rain, Unknown, Laplace).unwrap();
+        assert_eq!(Some(&(5f64 / 7f64)), laplace.find(&"y".to_string()));
+        // (0 + 2 * 0.5) / (3 + 2), sunny having seen no y at all
+        let m_estimate = predict_proba(&tree, &record(vec![("outlook", "sunny")]), Unknown, MEstimate(2f64)).unwrap();
+        assert_eq!(Some(&0.2f64), m_estimate.find(&"y".to_string()));
+        assert_eq!(Some(&0.8f64), m_estimate.find(&"n".to_string()));
+    }
+
+    #[test]
+    fn predict_proba_unseen_value() {
+        let tree = weather_tree();
+        let overcast = record(vec![("outlook", "overcast")]);
+
+        assert_eq!(None, predict_proba(&tree, &overcast, Unknown, Laplace));
+        let majority = predict_proba(&tree, &overcast, BranchMajority, NoSmoothing).unwrap();
+        assert_eq!(Some(&0.5f64), majority.find(&"n".to_string()));
+    }
+
+    #[test]
+    fn node_stats_even_split() {
+        let stats = counts(vec![("n", 2), ("y", 2)]);
+
+        assert_eq!(4, stats.samples);
+        assert_eq!(1f64, stats.entropy);
+        assert_eq!(0.5f64, stats.proportion("y"));
+        assert_eq!(0f64, stats.proportion("maybe"));
+    }
+
+    #[test]
+    fn node_stats_empty() {
+        let stats = NodeStats::new(TreeMap::new());
+
+        assert_eq!(0, stats.samples);
+        assert_eq!(0f64, stats.entropy);
+    }
+
+    #[test]
+    fn impurity_measures() {
+        assert_eq!(1f64, Entropy.impurity(&[2f64, 2f64]));
+        assert_eq!(0.5f64, Gini.impurity(&[2f64, 2f64]));
+        assert_eq!(0.25f64, ClassificationError.impurity(&[1f64, 3f64]));
+        assert_eq!(0f64, Gini.impurity(&[4f64]));
+        assert_eq!(0f64, Entropy.impurity(&[]));
+    }
+
+    #[test]
+    fn node_stats_records_measure() {
+        let class_counts = counts(vec![("n", 1), ("y", 3)]).class_counts;
+        let stats = NodeStats::with_measure(class_counts, Gini);
+
+        assert_eq!(Gini, stats.measure);
+        assert_eq!(0.375f64, stats.impurity);
+        assert!(0.811f64 < stats.entropy && stats.entropy < 0.812f64);
+    }
+
+    #[test]
+    fn show_tree_dump() {
+        let expected = "outlook? [8 samples, entropy 1.000, n: 4, y: 4]\n  outlook = rain: y [5 samples, entropy 0.722, n: 1, y: 4]\n  outlook = sunny: n [3 samples, entropy 0.000, n: 3]";
+
+        assert_eq!(expected.to_string(), format!("{}", weather_tree()));
+    }
+}
+
+#[experimental]
+#[doc = "Columnar, dictionary-encoded datasets for training"]
+pub mod dataset {
+    use std::collections::{TreeMap, TreeSet};
+    use std::str::eq_slice;
+    use tree::Record;
+    #[cfg(test)]
+    use tree::record;
 
-        // Loop through attribute values
-        let attr_values: HashSet<&str> = attributes.iter().map(|&x| x.val0()).collect();
-        for attr_value in attr_values.iter() {
-            let mut attr_entropy = 0f64;
-            // N_mj
-            let attr_count = attributes.iter().filter(|x| eq_slice(x.val0(), *attr_value)).count() as f64;
+    #[doc = "
+    One attribute of a dataset. Each row holds a small integer code into a dictionary of the
+    attribute's distinct values, assigned in the values' lexicographic order.
+    "]
+    #[deriving(Clone, Show)]
+    pub struct Column {
+        name: String,
+        dictionary: Vec<String>,
+        // Each dictionary value parsed as a number, if it is one
+        numbers: Vec<Option<f64>>,
+        codes: Vec<uint>,
+    }
+
+    impl Column {
+        fn new(name: &str, values: Vec<&str>) -> Column {
+            let distinct: TreeSet<&str> = values.iter().map(|x| *x).collect();
+            let lookup: TreeMap<&str, uint> = distinct.iter().enumerate().map(|(code, value)| (*value, code)).collect();
+            let dictionary: Vec<String> = distinct.iter().map(|x| x.to_string()).collect();
 
-            // Loop through label values
-            let label_values: HashSet<&str> = attributes.iter().filter(|x| x.val0() == *attr_value).map(|x| x.val1()).collect();
-            //DEBUG PRINT
-            //println!("{}", label_values)
-            for label_value in label_values.iter() {
-                // N^i_mj
-                let label_count = attributes.iter().filter(|x| eq_slice(x.val0(), *attr_value)).filter(|x| x.val1() == *label_value).count() as f64;
-                // p^i_mj
-                let label_prop = label_count / attr_count;
-                attr_entropy = attr_entropy + (label_prop * label_prop.log2())
+            Column {
+                name: name.to_string(),
+                numbers: dictionary.iter().map(|x| from_str::<f64>(x.as_slice().trim()).and_then(|x| if x.is_finite() { Some(x) } else { None })).collect(),
+                codes: values.iter().map(|x| *lookup.find(x).unwrap()).collect(),
+                dictionary: dictionary,
             }
+        }
+
+        pub fn name(&self) -> &str {
+            self.name.as_slice()
+        }
+
+        #[doc = "Code of the value in the given row."]
+        pub fn code(&self, row: uint) -> uint {
+            self.codes[row]
+        }
+
+        #[doc = "Value a code stands for."]
+        pub fn value(&self, code: uint) -> &str {
+            self.dictionary[code].as_slice()
+        }
+
+        #[doc = "Value a code stands for, parsed as a number. Infinities and `NaN` count as unparsed."]
+        pub fn number(&self, code: uint) -> Option<f64> {
+            self.numbers[code]
+        }
+
+        #[doc = "Code of a value, if any row holds it."]
+        pub fn code_of(&self, value: &str) -> Option<uint> {
+            self.dictionary.iter().position(|x| eq_slice(x.as_slice(), value))
+        }
 
-            total_entropy = total_entropy + ((attr_count / total_attr_count) * attr_entropy);
+        #[doc = "Number of distinct values, all codes being below it."]
+        pub fn cardinality(&self) -> uint {
+            self.dictionary.len()
         }
+    }
 
-        (-1f64) * total_entropy
+    #[doc = "
+    A table of examples read once from any `Record` source and stored column by column, each
+    column dictionary-encoded. The label is kept as the last column.
+    "]
+    #[deriving(Clone, Show)]
+    pub struct Dataset {
+        columns: Vec<Column>,
+        rows: uint,
     }
 
-    fn entropy<T: Record>(dataset: &Vec<T>, label_attribute_name: &str) -> f64{
-        let mut total_entropy = 0f64;
-        let total_count = dataset.len() as f64;
-        let labels: Vec<&str> = dataset.iter().map(|x| x.get_attribute(label_attribute_name)).collect();
+    impl Dataset {
+        #[doc = "Reads the given attributes and the label attribute of every record."]
+        pub fn from_records<T: Record>(records: &Vec<&T>, label_attribute_name: &str, attributes: Vec<&str>) -> Dataset {
+            let mut columns: Vec<Column> = attributes.iter()
+                .filter(|x| !eq_slice(**x, label_attribute_name))
+                .map(|x| Column::new(*x, records.iter().map(|record| record.get_attribute(*x)).collect()))
+                .collect();
+            columns.push(Column::new(label_attribute_name, records.iter().map(|x| x.get_attribute(label_attribute_name)).collect()));
+
+            Dataset { columns: columns, rows: records.len() }
+        }
+
+        #[doc = "Number of rows."]
+        pub fn len(&self) -> uint {
+            self.rows
+        }
+
+        pub fn column(&self, index: uint) -> &Column {
+            &self.columns[index]
+        }
+
+        #[doc = "Position of the column with the given name."]
+        pub fn column_index(&self, name: &str) -> Option<uint> {
+            self.columns.iter().position(|x| eq_slice(x.name(), name))
+        }
 
-        let label_values: HashSet<&&str> = labels.iter().collect();
-        for label_value in label_values.iter() {
-            let count = labels.iter().filter(|x| eq_slice(**label_value, **x)).count() as f64;
-            let proportion = count / total_count;
-            total_entropy = total_entropy + (proportion * proportion.log2());
+        #[doc = "The label column."]
+        pub fn labels(&self) -> &Column {
+            self.columns.last().unwrap()
         }
 
-        -total_entropy
+        #[doc = "A row, readable as a `Record`."]
+        pub fn row(&self, index: uint) -> Row {
+            Row { dataset: self, index: index }
+        }
     }
 
-    #[test]
-    fn split_entropy_empty_set() {
-        let dataset = vec![];
-        let result = split_entropy(dataset);
+    #[doc = "A row of a `Dataset`."]
+    pub struct Row<'a> {
+        dataset: &'a Dataset,
+        index: uint,
+    }
 
-        assert_eq!(0f64, result);
+    impl<'a> Record for Row<'a> {
+        fn get_attribute(&self, attr_name: &str) -> &str {
+            match self.dataset.column_index(attr_name) {
+                Some(position) => {
+                    let column = self.dataset.column(position);
+                    column.value(column.code(self.index))
+                },
+                None => fail!("no attribute named {}", attr_name),
+            }
+        }
+
+        fn get_attribute_names(&self) -> Vec<&str> {
+            let columns = self.dataset.columns.as_slice();
+            columns.slice_to(columns.len() - 1).iter().map(|x| x.name()).collect()
+        }
+
+        fn get_label(&self) -> &str {
+            let labels = self.dataset.labels();
+            labels.value(labels.code(self.index))
+        }
     }
 
     #[test]
-    fn split_entropy_set_size_one() {
-        let dataset = vec![("DrKwint", "y")];
-        let result = split_entropy(dataset);
+    fn dataset_interns_values() {
+        let r1 = record(vec![("outlook", "sunny"), ("humidity", "70"), ("class", "n")]);
+        let r2 = record(vec![("outlook", "rain"), ("humidity", "90.5"), ("class", "y")]);
+        let r3 = record(vec![("outlook", "sunny"), ("humidity", "?"), ("class", "n")]);
 
-        assert_eq!(0f64, result);
+        let data = Dataset::from_records(&vec![&r1, &r2, &r3], "class", vec!["outlook", "humidity"]);
+
+        assert_eq!(3, data.len());
+        let outlook = data.column(data.column_index("outlook").unwrap());
+        assert_eq!(2, outlook.cardinality());
+        assert_eq!(vec![1u, 0, 1], range(0, 3).map(|x| outlook.code(x)).collect());
+        assert_eq!("rain", outlook.value(0));
+        assert_eq!(None, outlook.code_of("overcast"));
+        let humidity = data.column(1);
+        assert_eq!(vec![Some(70f64), Some(90.5f64), None], range(0, 3).map(|x| humidity.number(humidity.code(x))).collect());
+        assert_eq!("class", data.labels().name());
     }
 
     #[test]
-    fn split_entropy_slides_humidity() {
-        let dataset = vec![
-            ("high", "n"),
-            ("high", "n"),
-            ("high", "y"),
-            ("high", "y"),
-            ("normal", "y"),
-            ("normal", "n"),
-            ("normal", "y"),
-            ("high", "n"),
-            ("normal", "y"),
-            ("normal", "y"),
-            ("normal", "y"),
-            ("high", "y"),
-            ("normal", "y"),
-            ("high", "n")];
-        let result = split_entropy(dataset);
+    fn dataset_rows_are_records() {
+        let r1 = record(vec![("outlook", "sunny"), ("class", "n")]);
+        let data = Dataset::from_records(&vec![&r1], "class", vec!["outlook"]);
 
-        assert!(0.788f64 < result && result < 0.789f64);
+        let row = data.row(0);
+        assert_eq!("sunny", row.get_attribute("outlook"));
+        assert_eq!("n", row.get_label());
+        assert_eq!(vec!["outlook"], row.get_attribute_names());
+    }
+}
+
+#[experimental]
+#[doc = "ID3 decision tree implementation"]
+pub mod id3 {
+    use std::collections::{TreeMap, TreeSet};
+    use std::sync::{Arc, Future};
+    use std::cmp;
+    use std::f64;
+    use tree::{Record, DecisionVertex, Leaf, Branch, Threshold, ClassCounts, NodeStats};
+    use tree::{Impurity, ImpurityMeasure, Entropy};
+    use tree::{MissingSplit, DistributeMissing, LargestChild};
+    use dataset::{Dataset, Column};
+    #[cfg(test)]
+    use std::str::eq_slice;
+    #[cfg(test)]
+    use tree::{TestRecord, record, Gini, predict, predict_proba, Unknown, Laplace};
+
+    #[doc = "How a majority label is chosen when several labels are equally common."]
+    #[deriving(Clone, PartialEq, Show)]
+    pub enum TieBreak {
+        /// Choose the lexicographically smallest of the tied labels.
+        LowestLabel,
+        /// Choose whichever tied label appears first in the dataset.
+        FirstSeenLabel,
+        /// Choose the parent vertex's majority label if it is among the tied labels,
+        /// otherwise the lexicographically smallest.
+        ParentMajority,
+        /// Choose one of the tied labels at random, reproducibly for a given `TreeBuilder::seed`.
+        RandomLabel,
+    }
+
+    #[doc = "Measure used to choose the attribute a vertex is split on."]
+    #[deriving(Clone, PartialEq, Show)]
+    pub enum SplitCriterion {
+        /// Largest information gain, as in ID3. Favors attributes with many values.
+        InformationGain,
+        /// Largest gain ratio, the information gain divided by the entropy of the split itself, as in
+        /// C4.5. Only attributes with at least the average information gain are considered.
+        GainRatio,
+    }
+
+    #[doc = "
+    How records carrying the missing-value marker are treated when splitting on an attribute.
+
+    To numeric attributes any value that does not parse as a finite number is missing, and the
+    marker never gets a child of its own. Under `MissingAsValue` missing values are left out when
+    scoring, as under `IgnoreMissing`, and join the larger side; predictions for them find no child.
+    "]
+    #[deriving(Clone, PartialEq, Show)]
+    pub enum MissingValues {
+        /// The marker is an ordinary value and gets its own branch.
+        MissingAsValue,
+        /// Records missing the value are left out when scoring the split, whose gain is scaled by the
+        /// share of records that have it, and join the most common child (the larger side of a
+        /// threshold). Predictions send them down
+        /// every child, weighted by training frequency (C4.5).
+        IgnoreMissing,
+        /// Missing values are replaced by the most frequent value among the vertex's records, both
+        /// when scoring and partitioning. Predictions send them to the largest child.
+        ImputeMostFrequent,
+    }
+
+    #[doc = "The missing-value marker and which attributes opt into special handling of it."]
+    #[deriving(Clone, Show)]
+    pub struct MissingValueConfig {
+        /// Value standing for a missing attribute, e.g. `?`.
+        pub marker: String,
+        /// Strategy for each attribute. Attributes not listed use `MissingAsValue`.
+        pub strategies: TreeMap<String, MissingValues>,
+    }
+
+    impl MissingValueConfig {
+        #[doc = "Configuration treating the marker as an ordinary value for every attribute."]
+        pub fn new(marker: &str) -> MissingValueConfig {
+            MissingValueConfig {
+                marker: marker.to_string(),
+                strategies: TreeMap::new(),
+            }
+        }
+
+        #[doc = "Opts an attribute into the given strategy."]
+        pub fn set(&mut self, attr_name: &str, strategy: MissingValues) {
+            self.strategies.insert(attr_name.to_string(), strategy);
+        }
+
+        pub fn strategy(&self, attr_name: &str) -> MissingValues {
+            match self.strategies.find(&attr_name.to_string()) {
+                Some(strategy) => *strategy,
+                None => MissingAsValue,
+            }
+        }
+
+        // How a split on the attribute routes missing values at prediction time
+        fn split_for(&self, attr_name: &str) -> Option<MissingSplit> {
+            let route = match self.strategy(attr_name) {
+                MissingAsValue => return None,
+                IgnoreMissing => DistributeMissing,
+                ImputeMostFrequent => LargestChild,
+            };
+            Some(MissingSplit { marker: self.marker.clone(), route: route })
+        }
+    }
+
+    // Slack allowed when comparing an attribute's gain against the average gain
+    static GAIN_EPSILON: f64 = 1e-9;
+
+    // Vertices with fewer examples than this are always grown and evaluated on the current task
+    static PARALLEL_MIN_EXAMPLES: uint = 256;
+
+    // Settings shared by every vertex of a tree
+    struct Settings {
+        label_column: uint,
+        // Columns of the attributes available to split on, and whether each is numeric
+        attributes: Vec<uint>,
+        numeric: Vec<bool>,
+        min_gain: f64,
+        min_impurity: f64,
+        max_depth: Option<uint>,
+        min_samples_split: uint,
+        min_samples_leaf: uint,
+        tie_break: TieBreak,
+        criterion: SplitCriterion,
+        impurity: ImpurityMeasure,
+        missing: MissingValueConfig,
+        chi_square_alpha: Option<f64>,
+    }
+
+    // The dataset and settings, shared with the tasks growing a tree in parallel
+    struct Shared {
+        data: Arc<Dataset>,
+        settings: Arc<Settings>,
+    }
+
+    // How a vertex divides its examples
+    struct Split {
+        // Position of the attribute in `Settings::attributes`
+        attr_index: uint,
+        // Numeric attributes split into values <= and > a threshold, discrete ones by value
+        threshold: Option<f64>,
+        // Impurity decrease of the split
+        gain: f64,
+    }
+
+    #[doc = "
+    Configuration for growing an ID3 decision tree
+
+    Starts from plain ID3: information gain over entropy, every attribute discrete, the missing-value
+    marker `?` treated as an ordinary value, ties going to the lowest label and splitting until the
+    leaves are pure. Each option is set with a method of the same name, e.g.
+    `TreeBuilder::new(\"class\", attributes).criterion(GainRatio).build(&dataset)`.
+    "]
+    #[deriving(Clone, Show)]
+    pub struct TreeBuilder {
+        label_attribute_name: String,
+        attributes: Vec<String>,
+        numeric_attributes: Vec<String>,
+        min_gain: f64,
+        min_impurity: f64,
+        max_depth: Option<uint>,
+        min_samples_split: uint,
+        min_samples_leaf: uint,
+        tie_break: TieBreak,
+        criterion: SplitCriterion,
+        impurity: ImpurityMeasure,
+        missing: MissingValueConfig,
+        chi_square_alpha: Option<f64>,
+        seed: u64,
+        threads: uint,
+    }
+
+    impl TreeBuilder {
+        #[doc = "Builder for trees predicting `label_attribute_name` from `attributes`."]
+        pub fn new(label_attribute_name: &str, attributes: Vec<&str>) -> TreeBuilder {
+            TreeBuilder {
+                label_attribute_name: label_attribute_name.to_string(),
+                attributes: attributes.iter().map(|x| x.to_string()).collect(),
+                numeric_attributes: vec![],
+                min_gain: 0f64,
+                min_impurity: 0f64,
+                max_depth: None,
+                min_samples_split: 2,
+                min_samples_leaf: 1,
+                tie_break: LowestLabel,
+                criterion: InformationGain,
+                impurity: Entropy,
+                missing: MissingValueConfig::new("?"),
+                chi_square_alpha: None,
+                seed: 0,
+                threads: 1,
+            }
+        }
+
+        #[doc = "
+        Pre-pruning threshold. A vertex becomes a majority-class leaf when the impurity decrease of
+        its best split falls below this value. Defaults to `0f64`.
+        "]
+        pub fn min_gain(mut self, min_gain: f64) -> TreeBuilder {
+            self.min_gain = min_gain;
+            self
+        }
+
+        #[doc = "
+        Pre-pruning threshold. A vertex becomes a majority-class leaf without trying any split when
+        its label impurity falls below this value. Defaults to `0f64`.
+        "]
+        pub fn min_impurity(mut self, min_impurity: f64) -> TreeBuilder {
+            self.min_impurity = min_impurity;
+            self
+        }
+
+        #[doc = "Deepest a vertex may be, the root being at depth 0. Vertices at this depth become majority leaves."]
+        pub fn max_depth(mut self, max_depth: uint) -> TreeBuilder {
+            self.max_depth = Some(max_depth);
+            self
+        }
+
+        #[doc = "Fewest examples a vertex needs to be split. Defaults to 2."]
+        pub fn min_samples_split(mut self, min_samples_split: uint) -> TreeBuilder {
+            self.min_samples_split = min_samples_split;
+            self
+        }
+
+        #[doc = "
+        Fewest examples each child of a split must receive, or the vertex becomes a majority leaf.
+        Children no example reaches are exempt. Numeric thresholds are only chosen among those
+        leaving enough examples on both sides. Defaults to 1.
+        "]
+        pub fn min_samples_leaf(mut self, min_samples_leaf: uint) -> TreeBuilder {
+            self.min_samples_leaf = min_samples_leaf;
+            self
+        }
+
+        #[doc = "How majority leaves are labeled when several labels are equally common."]
+        pub fn tie_break(mut self, tie_break: TieBreak) -> TreeBuilder {
+            self.tie_break = tie_break;
+            self
+        }
+
+        #[doc = "How the attribute to split on is chosen."]
+        pub fn criterion(mut self, criterion: SplitCriterion) -> TreeBuilder {
+            self.criterion = criterion;
+            self
+        }
+
+        #[doc = "Impurity measure behind the split criterion, recorded on every vertex's stats."]
+        pub fn impurity(mut self, impurity: ImpurityMeasure) -> TreeBuilder {
+            self.impurity = impurity;
+            self
+        }
+
+        #[doc = "The attributes holding numbers rather than categories."]
+        pub fn numeric_attributes(mut self, numeric_attributes: Vec<&str>) -> TreeBuilder {
+            self.numeric_attributes = numeric_attributes.iter().map(|x| x.to_string()).collect();
+            self
+        }
+
+        #[doc = "The missing-value marker and how each attribute treats it."]
+        pub fn missing_values(mut self, missing: MissingValueConfig) -> TreeBuilder {
+            self.missing = missing;
+            self
+        }
+
+        #[doc = "
+        Significance level of Quinlan's chi-square test. A vertex becomes a majority-class leaf if
+        the p-value of its best split's partition/label contingency table exceeds this value.
+        "]
+        pub fn chi_square_alpha(mut self, alpha: f64) -> TreeBuilder {
+            self.chi_square_alpha = Some(alpha);
+            self
+        }
+
+        #[doc = "
+        Seed for the random choices made while growing. Only `RandomLabel` tie-breaks use it, so
+        with any other `TieBreak` the tree is the same for every seed. Defaults to 0.
+        "]
+        pub fn seed(mut self, seed: u64) -> TreeBuilder {
+            self.seed = seed;
+            self
+        }
+
+        #[doc = "
+        Most tasks to grow the tree on at once. Above 1, sibling subtrees are grown concurrently,
+        the tasks being shared out between them, and a vertex left more than one task scores its
+        candidate attributes concurrently. The tree is the same as when grown serially, which is
+        the default.
+        "]
+        pub fn threads(mut self, threads: uint) -> TreeBuilder {
+            self.threads = if threads > 0 { threads } else { 1 };
+            self
+        }
+
+        #[unstable]
+        #[doc = "
+        Grows a decision tree from the dataset. See `id3` for how the tree is shaped.
+
+        # Safety Note
+
+        This is, in its present form, an incomplete and relatively untested implementation. It may fail unexpectedly. Do not use in a production setting.
+        "]
+        pub fn build<T: Record>(&self, dataset: &Vec<&T>) -> DecisionVertex {
+            let attributes: Vec<&str> = self.attributes.iter().map(|x| x.as_slice()).collect();
+            self.build_dataset(Arc::new(Dataset::from_records(dataset, self.label_attribute_name.as_slice(), attributes)))
+        }
+
+        #[unstable]
+        #[doc = "
+        Grows a decision tree from an already encoded dataset, which must have a column for the
+        label and for each attribute. Branches are made for every value in an attribute's column.
+        The dataset is shared, not copied, with the tasks growing the tree.
+        "]
+        pub fn build_dataset(&self, data: Arc<Dataset>) -> DecisionVertex {
+            // Declaration order decides ties, so repeated attributes keep their first position
+            let mut attributes: Vec<&String> = Vec::new();
+            for attribute in self.attributes.iter() {
+                if *attribute != self.label_attribute_name && !attributes.contains(&attribute) {
+                    attributes.push(attribute);
+                }
+            }
+            let settings = Settings {
+                label_column: column_for(&*data, self.label_attribute_name.as_slice()),
+                attributes: attributes.iter().map(|x| column_for(&*data, x.as_slice())).collect(),
+                numeric: attributes.iter().map(|x| self.numeric_attributes.contains(*x)).collect(),
+                min_gain: self.min_gain,
+                min_impurity: self.min_impurity,
+                max_depth: self.max_depth,
+                min_samples_split: self.min_samples_split,
+                min_samples_leaf: self.min_samples_leaf,
+                tie_break: self.tie_break,
+                criterion: self.criterion,
+                impurity: self.impurity,
+                missing: self.missing.clone(),
+                chi_square_alpha: self.chi_square_alpha,
+            };
+
+            // Vertices reorder their slice of one shared index buffer rather than copying examples
+            let mut indices: Vec<uint> = range(0, data.len()).collect();
+            let mut available = Vec::from_elem(settings.attributes.len(), true);
+            if self.threads > 1 {
+                let shared = Shared { data: data, settings: Arc::new(settings) };
+                grow(&*shared.data, indices.as_mut_slice(), &mut available, &*shared.settings, Some(&shared),
+                     None, self.seed, 0, self.threads)
+            } else {
+                grow(&*data, indices.as_mut_slice(), &mut available, &settings, None, None, self.seed, 0, 1)
+            }
+        }
+    }
+
+    fn column_for(data: &Dataset, attr_name: &str) -> uint {
+        match data.column_index(attr_name) {
+            Some(index) => index,
+            None => fail!("no attribute named {}", attr_name),
+        }
+    }
+
+    #[unstable]
+    #[doc = "
+    ID3 Decision Tree Algorithm
+
+    Recursively generates a decision tree from a dataset. [Wikipedia Link](http://en.wikipedia.org/wiki/ID3_algorithm)
+
+    Every branch has a child for each value of its attribute seen anywhere in the dataset. Partitions
+    that no example reaches become leaves labeled with their parent's majority label, as do impure
+    partitions with no attributes left to split on. An empty dataset yields a leaf with no examples, for which `predict` returns `None`.
+
+    Numeric attributes are split in two at the threshold that best separates the labels, chosen
+    from the midpoints between adjacent distinct values (as in C4.5), and may be split on again
+    further down the tree. Values that do not parse as finite numbers take no part in choosing
+    the threshold and are sent to the larger side, unless `ImputeMostFrequent` replaces them.
+
+    Attributes whose missing values are opted into `IgnoreMissing` or `ImputeMostFrequent` get no
+    branch for the missing-value marker; see `MissingValues`.
+
+    The tree depends only on the examples and the options, never on hashing or thread scheduling,
+    so trees grown from the same data print identically. Ties are broken as follows:
+
+    * Attributes whose scores are equal to within rounding error go to the one declared first in
+      `attributes`; an attribute declared more than once counts at its first position.
+    * Numeric thresholds that separate the labels equally well go to the lowest.
+    * Majority labels go by the `TieBreak`, lowest label by default. Imputed values go to the
+      lexicographically smallest value, and `LargestChild` routes to the first of the largest children.
+    * Children of a branch are ordered by value, and those of a threshold split below then above.
+
+    Apart from `FirstSeenLabel` tie-breaks, the order of the examples does not matter either.
+
+    This grows a plain ID3 tree; use `TreeBuilder` for the other options.
+
+    # Arguments
+
+    * 'dataset' - The dataset to label or recursively split.
+    * 'label_attribute_name' - The name of the classification attribute.
+    * 'attributes' - The attributes available to split on.
+    * 'entropy_threshold' - Pre-pruning threshold on both the label entropy and the information gain;
+      see `TreeBuilder::min_impurity` and `TreeBuilder::min_gain`.
+      Pass `0f64` to grow the tree until its leaves are pure.
+
+    # Safety Note
+
+    This is, in its present form, an incomplete and relatively untested implementation. It may fail unexpectedly. Do not use in a production setting.
+    "]
+    pub fn id3<T: Record>(dataset: Vec<&T>, label_attribute_name: &str, attributes: Vec<&str>, entropy_threshold: f64) -> DecisionVertex {
+        TreeBuilder::new(label_attribute_name, attributes)
+            .min_impurity(entropy_threshold)
+            .min_gain(entropy_threshold)
+            .build(&dataset)
+    }
+
+    // Grows the subtree over `indices` using at most `tasks` tasks at once, this one included
+    fn grow(data: &Dataset, indices: &mut [uint], available: &mut Vec<bool>, settings: &Settings, shared: Option<&Shared>,
+            parent_majority: Option<&str>, seed: u64, depth: uint, tasks: uint) -> DecisionVertex {
+        let labels = data.column(settings.label_column);
+
+        // Return parent's majority leaf if no examples reached this partition
+        if indices.is_empty() {
+            return Leaf(match parent_majority {
+                Some(label) => label.to_string(),
+                None => String::new(),
+            }, NodeStats::with_measure(TreeMap::new(), settings.impurity));
+        }
+
+        let stats = NodeStats::with_measure(class_counts(labels, indices), settings.impurity);
+
+        // Return labeled leaf if all labels in dataset are equal
+        if stats.class_counts.len() == 1 {
+            return Leaf(stats.class_counts.keys().next().unwrap().clone(), stats);
+        }
+
+        let majority = majority_label(labels, indices, settings.tie_break, parent_majority, seed);
+
+        // Return majority leaf if the vertex is as deep as allowed or has too few examples to split
+        if settings.max_depth.map_or(false, |x| depth >= x) || indices.len() < settings.min_samples_split {
+            return Leaf(majority, stats);
+        }
+
+        // Return majority leaf if the labels are already nearly pure
+        let node_impurity = stats.impurity;
+        if node_impurity < settings.min_impurity {
+            return Leaf(majority, stats);
+        }
+
+        // Choose attribute to split on, returning majority leaf if none are left
+        let split = match split_attribute(data, indices, available, node_impurity, settings, shared, tasks) {
+            Some(split) => split,
+            None => return Leaf(majority, stats),
+        };
+
+        // Return majority leaf if the best split tells us too little about the label
+        if settings.min_gain > 0f64 && split.gain < settings.min_gain {
+            return Leaf(majority, stats);
+        }
+
+        // Reorder the examples into one contiguous run per child
+        let column = data.column(settings.attributes[split.attr_index]);
+        let missing_split = settings.missing.split_for(column.name());
+        let mut values: Vec<uint> = Vec::new();
+        let sizes = match split.threshold {
+            Some(threshold) => partition_numeric(column, indices, threshold, imputed_number(column, indices, settings)),
+            None => {
+                let marker = match missing_split {
+                    Some(_) => column.code_of(settings.missing.marker.as_slice()),
+                    None => None,
+                };
+                // Records missing the value follow the most common known value, unless the marker
+                // is treated as a value of its own
+                let missing = match marker {
+                    Some(code) => most_common_value(column, indices, code).map(|value| (code, value)),
+                    None => None,
+                };
+
+                // One child for each value of the attribute seen anywhere in the dataset
+                values = range(0, column.cardinality()).filter(|x| Some(*x) != marker).collect();
+                partition_discrete(column, indices, &values, missing)
+            },
+        };
+
+        if !allows_split(labels, indices, &sizes, settings) {
+            return Leaf(majority, stats);
+        }
+
+        // Numeric attributes stay available, as a narrower threshold may still help
+        if split.threshold.is_none() {
+            *available.get_mut(split.attr_index) = false;
+        }
+        let mut children = match shared {
+            Some(shared) if tasks > 1 && indices.len() >= PARALLEL_MIN_EXAMPLES => {
+                // The children are grown in consecutive groups, one per task, each group sharing
+                // out its task's part of the tasks; the first group stays on this task and the
+                // others get their own copy of their examples, collected back in order
+                let groups = cmp::min(tasks, sizes.len());
+                let bounds: Vec<uint> = range(0, groups + 1).map(|x| x * sizes.len() / groups).collect();
+                let examples = |group: uint| sizes.slice(bounds[group], bounds[group + 1]).iter().fold(0u, |sum, x| sum + *x);
+                let (first, rest) = indices.split_at_mut(examples(0));
+                let mut subtrees = Vec::new();
+                let mut start = 0u;
+                for group in range(1, groups) {
+                    let mut group_indices = rest.slice(start, start + examples(group)).to_vec();
+                    let group_sizes = sizes.slice(bounds[group], bounds[group + 1]).to_vec();
+                    let mut group_available = available.clone();
+                    let (group_data, group_settings) = (shared.data.clone(), shared.settings.clone());
+                    let parent = majority.clone();
+                    let (position, group_tasks) = (bounds[group], task_share(tasks, groups, group));
+                    subtrees.push(Future::spawn(proc() {
+                        let shared = Shared { data: group_data, settings: group_settings };
+                        grow_children(&*shared.data, group_indices.as_mut_slice(), group_sizes.as_slice(), position,
+                                      &mut group_available, &*shared.settings, Some(&shared), parent.as_slice(),
+                                      seed, depth + 1, group_tasks)
+                    }));
+                    start += examples(group);
+                }
+                let mut children = grow_children(data, first, sizes.slice_to(bounds[1]), 0, available, settings, Some(shared),
+                                                 majority.as_slice(), seed, depth + 1, task_share(tasks, groups, 0));
+                for subtree in subtrees.into_iter() {
+                    children.extend(subtree.unwrap().into_iter());
+                }
+                children
+            },
+            _ => grow_children(data, indices, sizes.as_slice(), 0, available, settings, shared, majority.as_slice(),
+                               seed, depth + 1, tasks),
+        };
+        *available.get_mut(split.attr_index) = true;
+
+        match split.threshold {
+            Some(threshold) => {
+                let above_vertex = children.pop().unwrap();
+                let below_vertex = children.pop().unwrap();
+                Threshold(column.name().to_string(), threshold, box below_vertex, box above_vertex, missing_split, stats)
+            },
+            None => {
+                let branch_map = values.iter().map(|x| column.value(*x).to_string()).zip(children.into_iter()).collect();
+                Branch(column.name().to_string(), branch_map, missing_split, stats)
+            },
+        }
+    }
+
+    // Whether the runs of examples a split makes are large enough and pass the chi-square test
+    fn allows_split(labels: &Column, indices: &[uint], sizes: &Vec<uint>, settings: &Settings) -> bool {
+        if !sizes.iter().all(|x| *x == 0 || *x >= settings.min_samples_leaf) {
+            return false;
+        }
+
+        match settings.chi_square_alpha {
+            Some(alpha) => {
+                let mut counts: Vec<ClassCounts> = Vec::new();
+                let mut start = 0u;
+                for size in sizes.iter() {
+                    counts.push(class_counts(labels, indices.slice(start, start + *size)));
+                    start += *size;
+                }
+                let (statistic, degrees) = chi_square(&counts);
+                degrees > 0 && chi_square_p_value(statistic, degrees) <= alpha
+            },
+            None => true,
+        }
+    }
+
+    // Chi-square statistic and degrees of freedom of the contingency table between partitions and
+    // labels, leaving out empty partitions
+    fn chi_square(partitions: &Vec<ClassCounts>) -> (f64, uint) {
+        let rows: Vec<&ClassCounts> = partitions.iter().filter(|x| !x.is_empty()).collect();
+        let mut label_totals: TreeMap<&str, uint> = TreeMap::new();
+        for row in rows.iter() {
+            for (label, count) in row.iter() {
+                let total = match label_totals.find(&label.as_slice()) {
+                    Some(total) => *total,
+                    None => 0u,
+                };
+                label_totals.insert(label.as_slice(), total + *count);
+            }
+        }
+        if rows.len() < 2 || label_totals.len() < 2 {
+            return (0f64, 0u);
+        }
+
+        let total = label_totals.values().fold(0u, |sum, x| sum + *x) as f64;
+        let mut statistic = 0f64;
+        for row in rows.iter() {
+            let row_total = row.values().fold(0u, |sum, x| sum + *x) as f64;
+            for (label, label_total) in label_totals.iter() {
+                let observed = row.find(&label.to_string()).map_or(0f64, |x| *x as f64);
+                let expected = row_total * *label_total as f64 / total;
+                statistic += (observed - expected) * (observed - expected) / expected;
+            }
+        }
+
+        (statistic, (rows.len() - 1) * (label_totals.len() - 1))
+    }
+
+    // Probability of a chi-square statistic at least this large under independence, the
+    // regularized upper incomplete gamma function Q(degrees / 2, statistic / 2)
+    fn chi_square_p_value(statistic: f64, degrees: uint) -> f64 {
+        let a = degrees as f64 / 2f64;
+        let x = statistic / 2f64;
+        if x <= 0f64 {
+            return 1f64;
+        }
+        let scale = (-x + a * x.ln() - ln_gamma(a)).exp();
+
+        if x < a + 1f64 {
+            // Series for the lower function P(a, x)
+            let mut term = 1f64 / a;
+            let mut sum = term;
+            let mut n = a;
+            for _ in range(0u, 500) {
+                n += 1f64;
+                term *= x / n;
+                sum += term;
+                if term.abs() < sum.abs() * 1e-12 { break; }
+            }
+            1f64 - sum * scale
+        } else {
+            // Continued fraction for Q(a, x), evaluated by Lentz's method
+            let tiny = 1e-300f64;
+            let mut b = x + 1f64 - a;
+            let mut c = 1f64 / tiny;
+            let mut d = 1f64 / b;
+            let mut h = d;
+            for i in range(1u, 500) {
+                let an = -(i as f64) * (i as f64 - a);
+                b += 2f64;
+                d = an * d + b;
+                if d.abs() < tiny { d = tiny; }
+                c = b + an / c;
+                if c.abs() < tiny { c = tiny; }
+                d = 1f64 / d;
+                let delta = d * c;
+                h *= delta;
+                if (delta - 1f64).abs() < 1e-12 { break; }
+            }
+            scale * h
+        }
+    }
+
+    // Natural logarithm of the gamma function for positive arguments (Lanczos approximation)
+    fn ln_gamma(x: f64) -> f64 {
+        let coefficients = [76.18009172947146f64, -86.50532032941677f64, 24.01409824083091f64,
+                            -1.231739572450155f64, 0.1208650973866179e-2f64, -0.5395239384953e-5f64];
+        let mut tmp = x + 5.5f64;
+        tmp -= (x + 0.5f64) * tmp.ln();
+        let mut series = 1.000000000190015f64;
+        let mut y = x;
+        for coefficient in coefficients.iter() {
+            y += 1f64;
+            series += *coefficient / y;
+        }
+        -tmp + (2.5066282746310005f64 * series / x).ln()
+    }
+
+    // Examples reaching a vertex with each code of a column
+    fn code_counts(column: &Column, indices: &[uint]) -> Vec<uint> {
+        let mut counts = Vec::from_elem(column.cardinality(), 0u);
+        for index in indices.iter() {
+            *counts.get_mut(column.code(*index)) += 1;
+        }
+        counts
+    }
+
+    // Most common code of a column other than the missing-value marker's, ties going to the
+    // lexicographically smallest value
+    fn most_common_value(column: &Column, indices: &[uint], marker: uint) -> Option<uint> {
+        let mut best_value = None;
+        let mut best_count = 0u;
+        for (code, count) in code_counts(column, indices).iter().enumerate() {
+            if code != marker && *count > best_count {
+                best_value = Some(code);
+                best_count = *count;
+            }
+        }
+
+        best_value
+    }
+
+    // Most common value of a column that parses as a number, ties going to the lexicographically
+    // smallest value
+    fn most_common_number(column: &Column, indices: &[uint]) -> Option<f64> {
+        let mut best_value = None;
+        let mut best_count = 0u;
+        for (code, count) in code_counts(column, indices).iter().enumerate() {
+            if column.number(code).is_some() && *count > best_count {
+                best_value = column.number(code);
+                best_count = *count;
+            }
+        }
+
+        best_value
+    }
+
+    // Reorders the examples into those with values <= and > the threshold, returning the size of
+    // each run. Examples whose value does not parse take the imputed value if there is one, and
+    // otherwise join the larger side.
+    fn partition_numeric(column: &Column, indices: &mut [uint], threshold: f64, imputed: Option<f64>) -> Vec<uint> {
+        let sides: Vec<Option<uint>> = indices.iter().map(|x| match column.number(column.code(*x)).or(imputed) {
+            Some(value) if value <= threshold => Some(0u),
+            Some(_) => Some(1u),
+            None => None,
+        }).collect();
+        let below = sides.iter().filter(|x| **x == Some(0)).count();
+        let above = sides.iter().filter(|x| **x == Some(1)).count();
+        let larger = if below >= above { 0u } else { 1u };
+
+        partition_by_key(indices, sides.iter().map(|x| x.unwrap_or(larger)).collect(), 2)
+    }
+
+    // Reorders the examples by code, in the order of `values`, returning the size of each run.
+    // With `missing` as (marker, value), examples holding the marker join that value's run.
+    fn partition_discrete(column: &Column, indices: &mut [uint], values: &Vec<uint>, missing: Option<(uint, uint)>) -> Vec<uint> {
+        let mut positions = Vec::from_elem(column.cardinality(), 0u);
+        for (position, value) in values.iter().enumerate() {
+            *positions.get_mut(*value) = position;
+        }
+        let keys = indices.iter().map(|x| {
+            let code = match (column.code(*x), missing) {
+                (code, Some((marker, substitute))) if code == marker => substitute,
+                (code, _) => code,
+            };
+            positions[code]
+        }).collect();
+
+        partition_by_key(indices, keys, values.len())
+    }
+
+    // Stably reorders the examples so those with equal keys are contiguous and in key order,
+    // returning how many examples have each key
+    fn partition_by_key(indices: &mut [uint], keys: Vec<uint>, key_count: uint) -> Vec<uint> {
+        let mut sizes = Vec::from_elem(key_count, 0u);
+        for key in keys.iter() {
+            *sizes.get_mut(*key) += 1;
+        }
+
+        let mut offsets = Vec::with_capacity(key_count);
+        let mut start = 0u;
+        for size in sizes.iter() {
+            offsets.push(start);
+            start += *size;
+        }
+
+        let original = indices.to_vec();
+        for (index, key) in original.iter().zip(keys.iter()) {
+            let offset = offsets.get_mut(*key);
+            indices[*offset] = *index;
+            *offset += 1;
+        }
+
+        sizes
+    }
+
+    // Counts the examples taking each label
+    fn class_counts(labels: &Column, indices: &[uint]) -> ClassCounts {
+        code_counts(labels, indices).iter().enumerate()
+            .filter(|&(_, count)| *count > 0)
+            .map(|(code, count)| (labels.value(code).to_string(), *count))
+            .collect()
+    }
+
+    // Most common label, ties settled by tie_break
+    // assumes indices is not empty
+    fn majority_label(labels: &Column, indices: &[uint], tie_break: TieBreak, parent_majority: Option<&str>, seed: u64) -> String {
+        let counts = code_counts(labels, indices);
+        let best_count = *counts.iter().max().unwrap();
+        let tied: Vec<uint> = range(0, counts.len()).filter(|x| counts[*x] == best_count).collect();
+
+        let best_value = match tie_break {
+            LowestLabel => tied[0],
+            FirstSeenLabel => indices.iter().map(|x| labels.code(*x)).find(|x| tied.contains(x)).unwrap(),
+            ParentMajority => match parent_majority.and_then(|x| labels.code_of(x)) {
+                Some(code) if tied.contains(&code) => code,
+                _ => tied[0],
+            },
+            RandomLabel => tied[(mix_seed(seed) % tied.len() as u64) as uint],
+        };
+
+        labels.value(best_value).to_string()
+    }
+
+    // Seed for the child at the given position, so each vertex's random choices depend only on
+    // the builder's seed and the vertex's place in the tree
+    fn child_seed(seed: u64, index: uint) -> u64 {
+        mix_seed(seed ^ mix_seed(index as u64 + 1))
+    }
+
+    // SplitMix64 finalizer, spreading nearby seeds over the whole range
+    fn mix_seed(seed: u64) -> u64 {
+        let mut z = seed + 0x9E3779B97F4A7C15u64;
+        z = (z ^ (z >> 30)) * 0xBF58476D1CE4E5B9u64;
+        z = (z ^ (z >> 27)) * 0x94D049BB133111EBu64;
+        z ^ (z >> 31)
+    }
+
+    // Grows the children whose examples follow one another in `indices`, `sizes` long, one at a
+    // time; `first` is the position of the first among all the vertex's children
+    fn grow_children(data: &Dataset, indices: &mut [uint], sizes: &[uint], first: uint, available: &mut Vec<bool>,
+                     settings: &Settings, shared: Option<&Shared>, parent_majority: &str, seed: u64, depth: uint,
+                     tasks: uint) -> Vec<DecisionVertex> {
+        let mut children = Vec::new();
+        let mut rest = indices;
+        for (offset, size) in sizes.iter().enumerate() {
+            let run = rest;
+            let (child_indices, tail) = run.split_at_mut(*size);
+            children.push(grow(data, child_indices, available, settings, shared, Some(parent_majority),
+                               child_seed(seed, first + offset), depth, tasks));
+            rest = tail;
+        }
+        children
+    }
+
+    // Tasks given to one of `groups` groups of work, the remainder going to the first groups
+    fn task_share(tasks: uint, groups: uint, group: uint) -> uint {
+        tasks / groups + if group < tasks % groups { 1 } else { 0 }
+    }
+
+    // Returns the best split under the given criterion
+    // assumes indices is not empty
+    fn split_attribute(data: &Dataset, indices: &[uint], available: &Vec<bool>, node_impurity: f64, settings: &Settings,
+                       shared: Option<&Shared>, tasks: uint) -> Option<Split> {
+        let attributes: Vec<uint> = range(0, settings.attributes.len()).filter(|x| available[*x]).collect();
+
+        // (split, split information) for each candidate, in attribute order
+        let candidates: Vec<(Split, f64)> = match shared {
+            Some(shared) if tasks > 1 && indices.len() >= PARALLEL_MIN_EXAMPLES => {
+                // Score the attributes in one chunk per task, all reading one copy of the examples
+                let chunk_size = (attributes.len() + tasks - 1) / tasks;
+                let shared_indices = Arc::new(indices.to_vec());
+                let scores: Vec<Future<Vec<(Split, f64)>>> = attributes.as_slice().chunks(if chunk_size > 0 { chunk_size } else { 1 }).map(|chunk| {
+                    let chunk = chunk.to_vec();
+                    let chunk_indices = shared_indices.clone();
+                    let (chunk_data, chunk_settings) = (shared.data.clone(), shared.settings.clone());
+                    Future::spawn(proc() {
+                        chunk.iter()
+                            .filter_map(|x| evaluate_attribute(&*chunk_data, chunk_indices.as_slice(), *x, node_impurity, &*chunk_settings))
+                            .collect()
+                    })
+                }).collect();
+                scores.into_iter().flat_map(|x| x.unwrap().into_iter()).collect()
+            },
+            _ => attributes.iter().filter_map(|x| evaluate_attribute(data, indices, *x, node_impurity, settings)).collect(),
+        };
+
+        // Average gain over the attributes that actually split the records
+        let splitting: Vec<f64> = candidates.iter().filter(|&&(_, split_info)| split_info > 0f64).map(|&(ref split, _)| split.gain).collect();
+        let mean_gain = splitting.iter().fold(0f64, |sum, x| sum + *x) / splitting.len() as f64;
+
+        let mut max_score = f64::NEG_INFINITY;
+        let mut best_split = None;
+        for (split, split_info) in candidates.into_iter() {
+            let score = match settings.criterion {
+                InformationGain => split.gain,
+                GainRatio => {
+                    if split_info <= 0f64 || split.gain < mean_gain - GAIN_EPSILON { continue; }
+                    split.gain / split_info
+                },
+            };
+            // Scores within rounding error of the best so far tie, and the earlier attribute keeps it
+            if score > max_score + GAIN_EPSILON {
+                best_split = Some(split);
+                max_score = score;
+            }
+        }
+
+        best_split
+    }
+
+    // Value standing in for unparsed values of a numeric attribute, if it imputes them
+    fn imputed_number(column: &Column, indices: &[uint], settings: &Settings) -> Option<f64> {
+        match settings.missing.strategy(column.name()) {
+            ImputeMostFrequent => most_common_number(column, indices),
+            _ => None,
+        }
+    }
+
+    // Best split on one attribute, with its split information, if the attribute can split the examples
+    fn evaluate_attribute(data: &Dataset, indices: &[uint], attr_index: uint, node_impurity: f64, settings: &Settings) -> Option<(Split, f64)> {
+        let labels = data.column(settings.label_column);
+        let total = indices.len() as f64;
+        let column = data.column(settings.attributes[attr_index]);
+        if settings.numeric[attr_index] {
+            let imputed = imputed_number(column, indices, settings);
+            let value_label_pairs: Vec<(f64, uint)> = indices.iter()
+                .filter_map(|x| column.number(column.code(*x)).or(imputed).map(|value| (value, labels.code(*x))))
+                .collect();
+            // Only records with a value count, so scale the gain by their share
+            let known_share = value_label_pairs.len() as f64 / total;
+            best_threshold(value_label_pairs, settings.impurity, settings.min_samples_leaf).map(|(threshold, gain, split_info)| (Split {
+                attr_index: attr_index,
+                threshold: Some(threshold),
+                gain: known_share * gain,
+            }, split_info))
+        } else {
+            let mut table = CountTable::new(column.cardinality(), labels.cardinality());
+            for index in indices.iter() {
+                table.add(column.code(*index), labels.code(*index));
+            }
+            match column.code_of(settings.missing.marker.as_slice()) {
+                Some(marker) => match settings.missing.strategy(column.name()) {
+                    MissingAsValue => {},
+                    IgnoreMissing => table.remove(marker),
+                    ImputeMostFrequent => table.impute(marker),
+                },
+                None => {},
+            }
+            // Attributes missing from every record cannot split them
+            let known = table.total();
+            if known == 0f64 { return None; }
+
+            // Records left out contribute nothing, so scale the gain by the share kept
+            let known_impurity = if known == total {
+                node_impurity
+            } else {
+                table.label_impurity(settings.impurity)
+            };
+            Some((Split {
+                attr_index: attr_index,
+                threshold: None,
+                gain: known / total * (known_impurity - table.split_impurity(settings.impurity)),
+            }, table.split_information()))
+        }
+    }
+
+    // Best binary split of (value, label) pairs from a numeric attribute, returned as
+    // (threshold, impurity decrease, split information). Candidate thresholds are the midpoints
+    // between adjacent distinct values.
+    fn best_threshold<L: Ord + Copy>(mut pairs: Vec<(f64, L)>, impurity: ImpurityMeasure, min_leaf: uint) -> Option<(f64, f64, f64)> {
+        if pairs.len() < 2 { return None; }
+        pairs.sort_by(|a, b| a.val0().partial_cmp(&b.val0()).unwrap());
+        let total = pairs.len() as f64;
+
+        // Label counts either side of the threshold, everything starting above it
+        let labels: TreeSet<L> = pairs.iter().map(|&(_, label)| label).collect();
+        let labels: Vec<L> = labels.into_iter().collect();
+        let mut below = Vec::from_elem(labels.len(), 0f64);
+        let mut above = Vec::from_elem(labels.len(), 0f64);
+        for &(_, label) in pairs.iter() {
+            *above.get_mut(labels.iter().position(|x| *x == label).unwrap()) += 1f64;
+        }
+        let node_impurity = impurity.impurity(above.as_slice());
+
+        let mut min_impurity = f64::MAX_VALUE;
+        let mut best = None;
+        for i in range(0, pairs.len() - 1) {
+            let (value, label) = pairs[i];
+            let label_index = labels.iter().position(|x| *x == label).unwrap();
+            *below.get_mut(label_index) += 1f64;
+            *above.get_mut(label_index) -= 1f64;
+
+            let next_value = pairs[i + 1].val0();
+            if value == next_value || i + 1 < min_leaf || pairs.len() - (i + 1) < min_leaf { continue; }
+            let below_count = (i + 1) as f64;
+            let above_count = total - below_count;
+            let split_impurity = (below_count / total) * impurity.impurity(below.as_slice())
+                               + (above_count / total) * impurity.impurity(above.as_slice());
+            if split_impurity < min_impurity - GAIN_EPSILON {
+                let split_info = Entropy.impurity(&[below_count, above_count]);
+                best = Some(((value + next_value) / 2f64, node_impurity - split_impurity, split_info));
+                min_impurity = split_impurity;
+            }
+        }
+
+        best
+    }
+
+    // Label counts for each code of an attribute, gathered in one pass over a vertex's examples
+    struct CountTable {
+        labels: uint,
+        rows: Vec<Vec<f64>>,
+    }
+
+    impl CountTable {
+        fn new(values: uint, labels: uint) -> CountTable {
+            CountTable { labels: labels, rows: Vec::from_fn(values, |_| Vec::from_elem(labels, 0f64)) }
+        }
+
+        #[cfg(test)]
+        fn from_pairs(pairs: &Vec<(&str, &str)>) -> CountTable {
+            let values: Vec<&str> = pairs.iter().map(|&(value, _)| value).collect::<TreeSet<&str>>().into_iter().collect();
+            let labels: Vec<&str> = pairs.iter().map(|&(_, label)| label).collect::<TreeSet<&str>>().into_iter().collect();
+            let mut table = CountTable::new(values.len(), labels.len());
+            for &(value, label) in pairs.iter() {
+                table.add(values.iter().position(|x| eq_slice(*x, value)).unwrap(), labels.iter().position(|x| eq_slice(*x, label)).unwrap());
+            }
+            table
+        }
+
+        fn add(&mut self, value: uint, label: uint) {
+            *self.rows.get_mut(value).get_mut(label) += 1f64;
+        }
+
+        // Leaves out the examples holding the marker
+        fn remove(&mut self, marker: uint) {
+            for count in self.rows.get_mut(marker).iter_mut() {
+                *count = 0f64;
+            }
+        }
+
+        // Counts the examples holding the marker under the most common other value, ties going to
+        // the lexicographically smallest
+        fn impute(&mut self, marker: uint) {
+            let missing = self.rows[marker].clone();
+            self.remove(marker);
+
+            let mut mode = None;
+            let mut mode_count = 0f64;
+            for (value, row) in self.rows.iter().enumerate() {
+                let count = row.iter().fold(0f64, |sum, x| sum + *x);
+                if count > mode_count {
+                    mode = Some(value);
+                    mode_count = count;
+                }
+            }
+
+            match mode {
+                Some(value) => {
+                    for (count, extra) in self.rows.get_mut(value).iter_mut().zip(missing.iter()) {
+                        *count += *extra;
+                    }
+                },
+                None => {},
+            }
+        }
+
+        // N_m
+        fn total(&self) -> f64 {
+            self.rows.iter().fold(0f64, |sum, row| row.iter().fold(sum, |sum, x| sum + *x))
+        }
+
+        // Impurity of the labels alone
+        fn label_impurity(&self, impurity: ImpurityMeasure) -> f64 {
+            let mut counts = Vec::from_elem(self.labels, 0f64);
+            for row in self.rows.iter() {
+                for (count, x) in counts.iter_mut().zip(row.iter()) {
+                    *count += *x;
+                }
+            }
+            impurity.impurity(counts.as_slice())
+        }
+
+        // Impurity of the labels within each attribute value, weighted by how common the value is
+        fn split_impurity(&self, impurity: ImpurityMeasure) -> f64 {
+            let total = self.total();
+            self.rows.iter().fold(0f64, |sum, row| {
+                // N_mj
+                let value_count = row.iter().fold(0f64, |sum, x| sum + *x);
+                if value_count == 0f64 { return sum; }
+                sum + (value_count / total) * impurity.impurity(row.as_slice())
+            })
+        }
+
+        // Entropy of the attribute values themselves, i.e. how finely the split divides the records
+        fn split_information(&self) -> f64 {
+            let value_counts: Vec<f64> = self.rows.iter().map(|row| row.iter().fold(0f64, |sum, x| sum + *x)).collect();
+            Entropy.impurity(value_counts.as_slice())
+        }
+    }
+
+    // Pair-based shorthands kept for the tests below
+    #[cfg(test)]
+    fn split_impurity(attributes: Vec<(&str,&str)>, impurity: ImpurityMeasure) -> f64 {
+        CountTable::from_pairs(&attributes).split_impurity(impurity)
+    }
+
+    #[cfg(test)]
+    fn split_entropy(attributes: Vec<(&str,&str)>) -> f64 {
+        split_impurity(attributes, Entropy)
+    }
+
+    #[cfg(test)]
+    fn split_information(attributes: &Vec<(&str,&str)>) -> f64 {
+        CountTable::from_pairs(attributes).split_information()
+    }
+
+    #[test]
+    fn split_entropy_empty_set() {
+        let dataset = vec![];
+        let result = split_entropy(dataset);
+
+        assert_eq!(0f64, result);
+    }
+
+    #[test]
+    fn split_entropy_set_size_one() {
+        let dataset = vec![("DrKwint", "y")];
+        let result = split_entropy(dataset);
+
+        assert_eq!(0f64, result);
+    }
+
+    #[test]
+    fn split_entropy_slides_humidity() {
+        let dataset = vec![
+            ("high", "n"),
+            ("high", "n"),
+            ("high", "y"),
+            ("high", "y"),
+            ("normal", "y"),
+            ("normal", "n"),
+            ("normal", "y"),
+            ("high", "n"),
+            ("normal", "y"),
+            ("normal", "y"),
+            ("normal", "y"),
+            ("high", "y"),
+            ("normal", "y"),
+            ("high", "n")];
+        let result = split_entropy(dataset);
+
+        assert!(0.788f64 < result && result < 0.789f64);
+    }
+
+    #[test]
+    fn majority_label_tie_breaks() {
+        let (r1, r2, r3) = (record(vec![("class", "y")]), record(vec![("class", "n")]), record(vec![("class", "y")]));
+        let data = Dataset::from_records(&vec![&r1, &r2, &r3], "class", vec![]);
+        assert_eq!("y".to_string(), majority_label(data.labels(), &[0, 1, 2], LowestLabel, None, 0));
+        assert_eq!("n".to_string(), majority_label(data.labels(), &[0, 1], LowestLabel, None, 0));
+        assert_eq!("y".to_string(), majority_label(data.labels(), &[0, 1], FirstSeenLabel, None, 0));
+        assert_eq!("y".to_string(), majority_label(data.labels(), &[0, 1], ParentMajority, Some("y"), 0));
+        assert_eq!("n".to_string(), majority_label(data.labels(), &[0, 1], ParentMajority, None, 0));
+
+        // Reproducible for a seed, and every tied label is reachable
+        let random: Vec<String> = range(0u64, 16).map(|x| majority_label(data.labels(), &[0, 1], RandomLabel, None, x)).collect();
+        assert_eq!(random, range(0u64, 16).map(|x| majority_label(data.labels(), &[0, 1], RandomLabel, None, x)).collect());
+        assert!(random.iter().any(|x| x.as_slice() == "n") && random.iter().any(|x| x.as_slice() == "y"));
+        assert_eq!("y".to_string(), majority_label(data.labels(), &[0, 1, 2], RandomLabel, None, 7));
+    }
+
+    #[test]
+    fn id3_entropy_threshold_stops_split() {
+        let r1 = record(vec![("a", "0"), ("class", "y")]);
+        let r2 = record(vec![("a", "1"), ("class", "y")]);
+        let r3 = record(vec![("a", "1"), ("class", "n")]);
+        let dataset = vec![&r1, &r2, &r3];
+
+        match id3(dataset.clone(), "class", vec!["a"], 0f64) {
+            Branch(ref attr, _, _, _) => assert_eq!("a".to_string(), *attr),
+            _ => fail!("expected a branch"),
+        }
+        match id3(dataset, "class", vec!["a"], 1f64) {
+            Leaf(ref label, _) => assert_eq!("y".to_string(), *label),
+            _ => fail!("expected a leaf"),
+        }
+    }
+
+    #[test]
+    fn id3_impurity_and_gain_thresholds() {
+        let r1 = record(vec![("a", "0"), ("class", "y")]);
+        let r2 = record(vec![("a", "1"), ("class", "y")]);
+        let r3 = record(vec![("a", "1"), ("class", "n")]);
+        let dataset = vec![&r1, &r2, &r3];
+        let builder = TreeBuilder::new("class", vec!["a"]);
+
+        // The root's entropy is about 0.918 and splitting on `a` gains about 0.252
+        assert_eq!(1, builder.clone().min_impurity(0.5f64).build(&dataset).depth());
+        assert_eq!(0, builder.clone().min_impurity(1f64).build(&dataset).depth());
+        assert_eq!(1, builder.clone().min_gain(0.2f64).build(&dataset).depth());
+        assert_eq!(0, builder.min_gain(0.5f64).build(&dataset).depth());
+    }
+
+    #[test]
+    fn id3_empty_dataset() {
+        let dataset: Vec<&TestRecord> = vec![];
+
+        let tree = id3(dataset, "class", vec!["a"], 0f64);
+
+        match tree {
+            Leaf(_, ref stats) => assert_eq!(0, stats.samples),
+            _ => fail!("expected a leaf"),
+        }
+        let unseen = record(vec![("a", "0"), ("class", "y")]);
+        assert_eq!(None, predict(&tree, &unseen));
+        assert_eq!(None, predict_proba(&tree, &unseen, Unknown, Laplace));
+    }
+
+    #[test]
+    fn id3_attributes_exhausted() {
+        let r1 = record(vec![("a", "0"), ("class", "y")]);
+        let r2 = record(vec![("a", "0"), ("class", "n")]);
+        let r3 = record(vec![("a", "0"), ("class", "y")]);
+
+        match id3(vec![&r1, &r2, &r3], "class", vec!["a"], 0f64) {
+            Branch(_, ref children, _, _) => match children.find(&"0".to_string()) {
+                Some(&Leaf(ref label, ref stats)) => {
+                    assert_eq!("y".to_string(), *label);
+                    assert_eq!(3, stats.samples);
+                    assert_eq!(Some(&2u), stats.class_counts.find(&"y".to_string()));
+                },
+                _ => fail!("expected a leaf"),
+            },
+            _ => fail!("expected a branch"),
+        }
+    }
+
+    #[test]
+    fn id3_empty_partition() {
+        let r1 = record(vec![("a", "0"), ("b", "2"), ("class", "y")]);
+        let r2 = record(vec![("a", "0"), ("b", "0"), ("class", "y")]);
+        let r3 = record(vec![("a", "1"), ("b", "0"), ("class", "n")]);
+        let r4 = record(vec![("a", "1"), ("b", "1"), ("class", "y")]);
+        let r5 = record(vec![("a", "1"), ("b", "0"), ("class", "n")]);
+        let r6 = record(vec![("a", "0"), ("b", "0"), ("class", "y")]);
+
+        // Only a=1 examples reach the split on b, but b=2 is still given a child
+        let tree = id3(vec![&r1, &r2, &r3, &r4, &r5, &r6], "class", vec!["a", "b"], 0f64);
+        assert_eq!(Some("n".to_string()), predict(&tree, &record(vec![("a", "1"), ("b", "2")])));
+        match tree {
+            Branch(ref attr, ref children, _, _) => {
+                assert_eq!("a".to_string(), *attr);
+                match children.find(&"1".to_string()) {
+                    Some(&Branch(_, ref grandchildren, _, _)) => match grandchildren.find(&"2".to_string()) {
+                        Some(&Leaf(ref label, _)) => assert_eq!("n".to_string(), *label),
+                        _ => fail!("expected a leaf"),
+                    },
+                    _ => fail!("expected a branch"),
+                }
+            },
+            _ => fail!("expected a branch"),
+        }
+    }
+
+    #[test]
+    fn split_information_even_split() {
+        let dataset = vec![("a", "y"), ("a", "n"), ("b", "y"), ("b", "y")];
+
+        assert_eq!(1f64, split_information(&dataset));
+    }
+
+    #[test]
+    fn id3_gain_ratio_penalizes_many_values() {
+        let r1 = record(vec![("id", "1"), ("b", "0"), ("c", "0"), ("d", "1"), ("class", "y")]);
+        let r2 = record(vec![("id", "2"), ("b", "0"), ("c", "1"), ("d", "0"), ("class", "y")]);
+        let r3 = record(vec![("id", "3"), ("b", "0"), ("c", "0"), ("d", "1"), ("class", "y")]);
+        let r4 = record(vec![("id", "4"), ("b", "1"), ("c", "1"), ("d", "0"), ("class", "n")]);
+        let r5 = record(vec![("id", "5"), ("b", "1"), ("c", "0"), ("d", "1"), ("class", "n")]);
+        let r6 = record(vec![("id", "6"), ("b", "1"), ("c", "1"), ("d", "0"), ("class", "y")]);
+        let dataset = vec![&r1, &r2, &r3, &r4, &r5, &r6];
+        let attributes = vec!["id", "b", "c", "d"];
+
+        match id3(dataset.clone(), "class", attributes.clone(), 0f64) {
+            Branch(ref attr, _, _, _) => assert_eq!("id".to_string(), *attr),
+            _ => fail!("expected a branch"),
+        }
+        match TreeBuilder::new("class", attributes).criterion(GainRatio).build(&dataset) {
+            Branch(ref attr, _, _, _) => assert_eq!("b".to_string(), *attr),
+            _ => fail!("expected a branch"),
+        }
+    }
+
+    #[test]
+    fn split_impurity_gini() {
+        let dataset = vec![("a", "y"), ("a", "n"), ("b", "y"), ("b", "y")];
+
+        // 0.5 * 0.5 + 0.5 * 0
+        assert_eq!(0.25f64, split_impurity(dataset, Gini));
+    }
+
+    #[test]
+    fn id3_records_impurity_measure() {
+        let r1 = record(vec![("a", "0"), ("class", "y")]);
+        let r2 = record(vec![("a", "1"), ("class", "n")]);
+
+        let tree = TreeBuilder::new("class", vec!["a"]).impurity(Gini).build(&vec![&r1, &r2]);
+        assert_eq!(Gini, tree.measure());
+        assert_eq!(0.5f64, tree.stats().impurity);
+        match tree {
+            Branch(_, ref children, _, _) => assert!(children.values().all(|x| x.measure() == Gini)),
+            _ => fail!("expected a branch"),
+        }
+    }
+
+    #[test]
+    fn best_threshold_midpoint() {
+        let pairs = vec![(10f64, "n"), (1f64, "y"), (12f64, "n"), (2f64, "y"), (2f64, "y"), (11f64, "n")];
+
+        assert_eq!(Some((6f64, 1f64, 1f64)), best_threshold(pairs, Entropy, 1));
+        assert_eq!(None, best_threshold(vec![(1f64, "y"), (1f64, "n")], Entropy, 1));
+
+        // The pure split at 2.5 would leave only two values below
+        let pairs = vec![(1f64, "y"), (2f64, "y"), (3f64, "n"), (10f64, "n"), (11f64, "n"), (12f64, "n")];
+        assert_eq!(Some(6.5f64), best_threshold(pairs, Entropy, 3).map(|x| x.val0()));
+    }
+
+    #[test]
+    fn id3_numeric_attribute() {
+        let r1 = record(vec![("x", "1"), ("class", "y")]);
+        let r2 = record(vec![("x", "2.5"), ("class", "y")]);
+        let r3 = record(vec![("x", "10"), ("class", "n")]);
+        let r4 = record(vec![("x", "?"), ("class", "n")]);
+        let r5 = record(vec![("x", "12"), ("class", "n")]);
+        let r6 = record(vec![("x", "11"), ("class", "n")]);
+
+        match TreeBuilder::new("class", vec!["x"]).numeric_attributes(vec!["x"]).build(&vec![&r1, &r2, &r3, &r4, &r5, &r6]) {
+            Threshold(ref attr, threshold, ref below, ref above, _, _) => {
+                assert_eq!("x".to_string(), *attr);
+                assert_eq!(6.25f64, threshold);
+                assert_eq!(2, below.stats().samples);
+                // The unparsed value joins the larger side
+                assert_eq!(4, above.stats().samples);
+            },
+            _ => fail!("expected a threshold"),
+        }
+    }
+
+    #[test]
+    fn id3_numeric_attribute_skips_non_finite_values() {
+        let r1 = record(vec![("x", "1"), ("class", "y")]);
+        let r2 = record(vec![("x", "2"), ("class", "y")]);
+        let r3 = record(vec![("x", "10"), ("class", "n")]);
+        let r4 = record(vec![("x", "NaN"), ("class", "n")]);
+        let r5 = record(vec![("x", "inf"), ("class", "n")]);
+
+        let tree = TreeBuilder::new("class", vec!["x"]).numeric_attributes(vec!["x"]).build(&vec![&r1, &r2, &r3, &r4, &r5]);
+        match tree {
+            Threshold(_, threshold, ref below, ref above, _, _) => {
+                assert_eq!(6f64, threshold);
+                // Like unparsed values, NaN and infinity join the larger side
+                assert_eq!(4, below.stats().samples);
+                assert_eq!(1, above.stats().samples);
+            },
+            _ => fail!("expected a threshold"),
+        }
+        assert_eq!(None, tree.child_index(&record(vec![("x", "NaN")])));
+    }
+
+    #[test]
+    fn id3_missing_value_strategies() {
+        let r1 = record(vec![("vote", "y"), ("class", "d")]);
+        let r2 = record(vec![("vote", "y"), ("class", "d")]);
+        let r3 = record(vec![("vote", "n"), ("class", "r")]);
+        let r4 = record(vec![("vote", "?"), ("class", "r")]);
+        let dataset = vec![&r1, &r2, &r3, &r4];
+
+        // Today's behavior, "?" gets its own branch
+        match id3(dataset.clone(), "class", vec!["vote"], 0f64) {
+            Branch(_, ref children, ref missing, _) => {
+                assert_eq!(3, children.len());
+                assert!(missing.is_none());
+            },
+            _ => fail!("expected a branch"),
+        }
+
+        // Ignored when scoring, then joining the most common value's child
+        let mut ignore = MissingValueConfig::new("?");
+        ignore.set("vote", IgnoreMissing);
+        match TreeBuilder::new("class", vec!["vote"]).missing_values(ignore).build(&dataset) {
+            Branch(_, ref children, ref missing, _) => {
+                assert_eq!(2, children.len());
+                assert_eq!(Some(DistributeMissing), missing.as_ref().map(|x| x.route));
+                assert_eq!(3, children.find(&"y".to_string()).unwrap().stats().samples);
+            },
+            _ => fail!("expected a branch"),
+        }
+
+        let mut impute = MissingValueConfig::new("?");
+        impute.set("vote", ImputeMostFrequent);
+        match TreeBuilder::new("class", vec!["vote"]).missing_values(impute).build(&dataset) {
+            Branch(_, ref children, ref missing, _) => {
+                assert_eq!(2, children.len());
+                assert_eq!(Some(LargestChild), missing.as_ref().map(|x| x.route));
+            },
+            _ => fail!("expected a branch"),
+        }
+    }
+
+    #[test]
+    fn id3_numeric_missing_value_strategies() {
+        let r1 = record(vec![("x", "1"), ("class", "y")]);
+        let r2 = record(vec![("x", "2"), ("class", "y")]);
+        let r3 = record(vec![("x", "10"), ("class", "n")]);
+        let r4 = record(vec![("x", "11"), ("class", "n")]);
+        let r5 = record(vec![("x", "12"), ("class", "n")]);
+        let r6 = record(vec![("x", "?"), ("class", "y")]);
+        let dataset = vec![&r1, &r2, &r3, &r4, &r5, &r6];
+        let builder = TreeBuilder::new("class", vec!["x"]).numeric_attributes(vec!["x"]);
+
+        let mut ignore = MissingValueConfig::new("?");
+        ignore.set("x", IgnoreMissing);
+        match builder.clone().missing_values(ignore).build(&dataset) {
+            Threshold(_, threshold, ref below, ref above, ref missing, _) => {
+                assert_eq!(6f64, threshold);
+                assert_eq!(Some(DistributeMissing), missing.as_ref().map(|x| x.route));
+                assert_eq!(2, below.stats().samples);
+                assert_eq!(4, above.stats().samples);
+            },
+            _ => fail!("expected a threshold"),
+        }
+
+        // Every number appears once, so "1" is the lexicographically smallest most frequent value
+        let mut impute = MissingValueConfig::new("?");
+        impute.set("x", ImputeMostFrequent);
+        match builder.missing_values(impute).build(&dataset) {
+            Threshold(_, threshold, ref below, ref above, ref missing, _) => {
+                assert_eq!(6f64, threshold);
+                assert_eq!(Some(LargestChild), missing.as_ref().map(|x| x.route));
+                assert_eq!(3, below.stats().samples);
+                assert_eq!(3, above.stats().samples);
+            },
+            _ => fail!("expected a threshold"),
+        }
+    }
+
+    #[test]
+    fn id3_growth_limits() {
+        let r1 = record(vec![("a", "0"), ("b", "0"), ("class", "y")]);
+        let r2 = record(vec![("a", "0"), ("b", "1"), ("class", "n")]);
+        let r3 = record(vec![("a", "1"), ("b", "0"), ("class", "n")]);
+        let r4 = record(vec![("a", "1"), ("b", "1"), ("class", "n")]);
+        let r5 = record(vec![("a", "1"), ("b", "1"), ("class", "n")]);
+        let dataset = vec![&r1, &r2, &r3, &r4, &r5];
+        let builder = TreeBuilder::new("class", vec!["a", "b"]);
+
+        assert_eq!(2, builder.clone().build(&dataset).depth());
+        assert_eq!(1, builder.clone().max_depth(1).build(&dataset).depth());
+        assert_eq!(0, builder.clone().max_depth(0).build(&dataset).depth());
+        // The impure child has only two examples
+        assert_eq!(1, builder.clone().min_samples_split(3).build(&dataset).depth());
+        assert_eq!(0, builder.clone().min_samples_split(6).build(&dataset).depth());
+        // Either first split leaves a child with two examples
+        match builder.min_samples_leaf(3).build(&dataset) {
+            Leaf(ref label, _) => assert_eq!("n".to_string(), *label),
+            _ => fail!("expected a leaf"),
+        }
+    }
+
+    #[test]
+    fn chi_square_p_values() {
+        assert!((chi_square_p_value(3.841f64, 1) - 0.05f64).abs() < 1e-4);
+        assert!((chi_square_p_value(5.991f64, 2) - 0.05f64).abs() < 1e-4);
+        assert!((chi_square_p_value(20f64, 3) - 0.00017f64).abs() < 1e-5);
+        assert_eq!(1f64, chi_square_p_value(0f64, 4));
+    }
+
+    #[test]
+    fn id3_chi_square_stops_insignificant_split() {
+        let r1 = record(vec![("a", "0"), ("class", "y")]);
+        let r2 = record(vec![("a", "0"), ("class", "y")]);
+        let r3 = record(vec![("a", "1"), ("class", "n")]);
+        let r4 = record(vec![("a", "1"), ("class", "n")]);
+        let dataset = vec![&r1, &r2, &r3, &r4];
+
+        // A perfect split of four records has chi-square 4 on one degree of freedom, p = 0.0455
+        match TreeBuilder::new("class", vec!["a"]).chi_square_alpha(0.05f64).build(&dataset) {
+            Branch(..) => {},
+            _ => fail!("expected a branch"),
+        }
+        match TreeBuilder::new("class", vec!["a"]).chi_square_alpha(0.01f64).build(&dataset) {
+            Leaf(ref label, _) => assert_eq!("n".to_string(), *label),
+            _ => fail!("expected a leaf"),
+        }
+    }
+
+    #[test]
+    fn id3_parallel_matches_serial() {
+        static DIGITS: [&'static str, ..10] = ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"];
+        // Enough records for the root and its children to be grown on separate tasks
+        let records: Vec<TestRecord> = range(0u, 1200).map(|i| {
+            let (a, b, c, x) = (i % 3, i / 3 % 3, i / 9 % 4, i / 7 % 10);
+            // Every eleventh label is noise, leaving impure leaves with ties for RandomLabel
+            let class = if ((a + b + c) % 2 == 0) != (i % 11 == 0) { "y" } else { "n" };
+            record(vec![("a", DIGITS[a]), ("b", DIGITS[b]), ("c", DIGITS[c]), ("x", DIGITS[x]), ("class", class)])
+        }).collect();
+        let dataset: Vec<&TestRecord> = records.iter().collect();
+        let builder = TreeBuilder::new("class", vec!["a", "b", "c", "x"])
+            .numeric_attributes(vec!["x"])
+            .tie_break(RandomLabel)
+            .seed(42);
+
+        let serial = format!("{}", builder.clone().build(&dataset));
+        // Eight tasks are more than the root has children
+        for threads in vec![2u, 3, 4, 8].into_iter() {
+            assert_eq!(serial, format!("{}", builder.clone().threads(threads).build(&dataset)));
+        }
+    }
+
+    #[test]
+    fn task_shares_add_up() {
+        assert_eq!(vec![2u, 1, 1], range(0u, 3).map(|x| task_share(4, 3, x)).collect::<Vec<uint>>());
+        assert_eq!(vec![1u, 1], range(0u, 2).map(|x| task_share(2, 2, x)).collect::<Vec<uint>>());
+        assert_eq!(vec![3u], range(0u, 1).map(|x| task_share(3, 1, x)).collect::<Vec<uint>>());
+    }
+
+    #[test]
+    fn id3_ties_follow_declaration_order() {
+        let r1 = record(vec![("a", "0"), ("b", "p"), ("class", "y")]);
+        let r2 = record(vec![("a", "1"), ("b", "q"), ("class", "n")]);
+        let dataset = vec![&r1, &r2];
+
+        // Both attributes separate the labels perfectly
+        for (attributes, expected) in vec![(vec!["a", "b"], "a"), (vec!["b", "a"], "b"), (vec!["b", "a", "b"], "b")].into_iter() {
+            match TreeBuilder::new("class", attributes).build(&dataset) {
+                Branch(ref attr, _, _, _) => assert_eq!(expected.to_string(), *attr),
+                _ => fail!("expected a branch"),
+            }
+        }
+    }
+
+    #[test]
+    fn id3_independent_of_record_order() {
+        let r1 = record(vec![("a", "0"), ("b", "0"), ("x", "1"), ("class", "y")]);
+        let r2 = record(vec![("a", "0"), ("b", "1"), ("x", "3"), ("class", "n")]);
+        let r3 = record(vec![("a", "1"), ("b", "0"), ("x", "2"), ("class", "n")]);
+        let r4 = record(vec![("a", "1"), ("b", "1"), ("x", "4"), ("class", "y")]);
+        let r5 = record(vec![("a", "1"), ("b", "1"), ("x", "4"), ("class", "n")]);
+        let builder = TreeBuilder::new("class", vec!["a", "b", "x"]).numeric_attributes(vec!["x"]);
+
+        let forward = format!("{}", builder.build(&vec![&r1, &r2, &r3, &r4, &r5]));
+        assert_eq!(forward, format!("{}", builder.build(&vec![&r5, &r4, &r3, &r2, &r1])));
+        assert_eq!(forward, format!("{}", builder.build(&vec![&r3, &r1, &r5, &r2, &r4])));
+    }
+}
+
+#[experimental]
+#[doc = "Post-pruning of grown decision trees"]
+pub mod prune {
+    use std::f64;
+    use std::mem;
+    use std::str::eq_slice;
+    use std::collections::TreeMap;
+    use tree::{Record, DecisionVertex, Leaf, Branch, Threshold, NodeStats, predict};
+    #[cfg(test)]
+    use tree::{record, counts};
+    #[cfg(test)]
+    use id3::id3;
+
+    // C4.5 prefers the smaller tree when error estimates are within this of each other
+    static ESTIMATE_SLACK: f64 = 0.1;
+    // Links weaker than the weakest by at most this are cut together
+    static ALPHA_EPSILON: f64 = 1e-9;
+
+    #[doc = "A tree in a cost-complexity pruning sequence, with the least complexity parameter for which it is optimal."]
+    #[deriving(Clone)]
+    pub struct PruningStep {
+        /// Complexity parameter at which this tree becomes the optimal subtree.
+        pub alpha: f64,
+        /// The pruned tree.
+        pub tree: DecisionVertex,
+    }
+
+    #[doc = "Why pruning could not be carried out."]
+    #[deriving(Clone, PartialEq, Show)]
+    pub enum PruneError {
+        /// A confidence factor outside (0, 0.5].
+        InvalidConfidence(f64),
+        /// A number of cross-validation folds below 2 or above the number of records.
+        InvalidFolds(uint),
+    }
+
+    #[doc = "Summary of what a pruning pass changed."]
+    #[deriving(Clone, PartialEq, Show)]
+    pub struct PruneReport {
+        /// Vertices in the tree before pruning.
+        pub nodes_before: uint,
+        /// Vertices in the tree after pruning.
+        pub nodes_after: uint,
+        /// Subtrees replaced by a leaf.
+        pub subtrees_pruned: uint,
+        /// Subtrees replaced by their largest child.
+        pub subtrees_raised: uint,
+    }
+
+    impl PruneReport {
+        fn new(tree: &DecisionVertex) -> PruneReport {
+            let nodes = tree.node_count();
+            PruneReport { nodes_before: nodes, nodes_after: nodes, subtrees_pruned: 0, subtrees_raised: 0 }
+        }
+
+        #[doc = "Number of vertices pruning removed."]
+        pub fn nodes_removed(&self) -> uint {
+            self.nodes_before - self.nodes_after
+        }
+    }
+
+    #[doc = "
+    Reduced-error pruning
+
+    Working bottom-up, replaces each subtree with a leaf labeled with the majority class of its
+    training examples whenever that does not increase the number of validation records the
+    subtree misclassifies. Subtrees no validation record reaches are replaced too. Records are
+    checked against their `Record::get_label`.
+    "]
+    pub fn reduced_error<T: Record>(tree: &mut DecisionVertex, validation: &[T]) -> PruneReport {
+        let mut report = PruneReport::new(tree);
+        prune_reduced_error(tree, validation.iter().collect(), &mut report);
+        report.nodes_after = tree.node_count();
+        report
+    }
+
+    // Prunes the subtree against the validation records reaching it
+    fn prune_reduced_error<T: Record>(vertex: &mut DecisionVertex, validation: Vec<&T>, report: &mut PruneReport) {
+        match *vertex {
+            Leaf(..) => return,
+            _ => {},
+        }
+
+        // Prune the children first, each against the records routed to it
+        let mut subsets: Vec<Vec<&T>> = range(0, vertex.children().len()).map(|_| Vec::new()).collect();
+        for record in validation.iter() {
+            match vertex.child_index(*record) {
+                Some(index) => subsets.get_mut(index).push(*record),
+                None => {},
+            }
+        }
+        for (child, subset) in vertex.children_mut().into_iter().zip(subsets.into_iter()) {
+            prune_reduced_error(child, subset, report);
+        }
+
+        let leaf = majority_leaf(vertex.stats());
+        if errors(&leaf, &validation) <= errors(vertex, &validation) {
+            *vertex = leaf;
+            report.subtrees_pruned += 1;
+        }
+    }
+
+    // Number of records the tree misclassifies
+    fn errors<T: Record>(tree: &DecisionVertex, records: &Vec<&T>) -> uint {
+        records.iter().filter(|x| match predict(tree, **x) {
+            Some(label) => !eq_slice(label.as_slice(), x.get_label()),
+            None => true,
+        }).count()
+    }
+
+    #[doc = "
+    Pessimistic (C4.5) pruning
+
+    Estimates the errors of each vertex from the training records alone, using the upper limit
+    of the binomial confidence interval on its observed error rate, so no data has to be held out.
+    Working bottom-up, a subtree is replaced by a majority leaf when the leaf's estimate is no
+    worse, or, with `subtree_raising`, by its largest child when that child classifying all the
+    vertex's records is no worse. The replaced vertex's records are then sent down the raised
+    subtree, whose stats are recomputed from them and whose leaves are relabeled with their new
+    majority class, as in C4.5. Records split between children count fractionally, rounded to
+    whole examples in the stats.
+
+    # Arguments
+
+    * `tree` - Tree to prune, grown from `training`
+    * `training` - Records the tree was grown from, checked against their `Record::get_label`
+    * `confidence` - Confidence factor in (0, 0.5]; smaller values prune more. C4.5 uses 0.25.
+    * `subtree_raising` - Whether to also try replacing subtrees with their largest child
+
+    Fails with `InvalidConfidence`, leaving the tree untouched, if the confidence factor is out
+    of range.
+    "]
+    pub fn pessimistic<T: Record>(tree: &mut DecisionVertex, training: &[T], confidence: f64,
+                                  subtree_raising: bool) -> Result<PruneReport, PruneError> {
+        if !(confidence > 0f64 && confidence <= 0.5f64) {
+            return Err(InvalidConfidence(confidence));
+        }
+
+        let mut report = PruneReport::new(tree);
+        let records = training.iter().map(|x| (x, 1f64)).collect();
+        prune_pessimistic(tree, records, confidence, subtree_raising, &mut report);
+        report.nodes_after = tree.node_count();
+        Ok(report)
+    }
+
+    // Prunes the subtree against the weighted training records reaching it
+    fn prune_pessimistic<T: Record>(vertex: &mut DecisionVertex, records: Vec<(&T, f64)>, confidence: f64,
+                                    subtree_raising: bool, report: &mut PruneReport) {
+        match *vertex {
+            Leaf(..) => return,
+            _ => {},
+        }
+
+        let subsets = route(vertex, &records);
+        for (child, subset) in vertex.children_mut().into_iter().zip(subsets.into_iter()) {
+            prune_pessimistic(child, subset, confidence, subtree_raising, report);
+        }
+
+        let leaf = majority_leaf(vertex.stats());
+        let leaf_errors = estimated_errors(&leaf, &records, confidence);
+        let tree_errors = estimated_errors(vertex, &records, confidence);
+        let largest = largest_child(vertex);
+        let branch_errors = if subtree_raising {
+            estimated_errors(vertex.children()[largest], &records, confidence)
+        } else {
+            f64::INFINITY
+        };
+
+        if leaf_errors <= tree_errors + ESTIMATE_SLACK && leaf_errors <= branch_errors + ESTIMATE_SLACK {
+            *vertex = leaf;
+            report.subtrees_pruned += 1;
+        } else if branch_errors <= tree_errors + ESTIMATE_SLACK {
+            let raised = match mem::replace(vertex, leaf) {
+                Branch(_, children, _, _) => children.into_iter().nth(largest).unwrap().val1(),
+                Threshold(_, _, below, above, _, _) => if largest == 0 { *below } else { *above },
+                Leaf(..) => unreachable!(),
+            };
+            *vertex = raised;
+            restate(vertex, &records);
+            report.subtrees_raised += 1;
+
+            // The raised subtree now sees all of this vertex's records
+            prune_pessimistic(vertex, records, confidence, subtree_raising, report);
+        }
+    }
+
+    // Splits weighted records between the children, dividing those no single child takes in
+    // proportion to the children's training samples
+    fn route<'a, T: Record>(vertex: &DecisionVertex, records: &Vec<(&'a T, f64)>) -> Vec<Vec<(&'a T, f64)>> {
+        let children = vertex.children();
+        let samples = vertex.stats().samples as f64;
+        let mut subsets: Vec<Vec<(&T, f64)>> = range(0, children.len()).map(|_| Vec::new()).collect();
+        for &(record, weight) in records.iter() {
+            match vertex.child_index(record) {
+                Some(index) => subsets.get_mut(index).push((record, weight)),
+                None if samples > 0f64 => {
+                    for (index, child) in children.iter().enumerate() {
+                        let share = child.stats().samples as f64 / samples;
+                        if share > 0f64 {
+                            subsets.get_mut(index).push((record, weight * share));
+                        }
+                    }
+                },
+                None => {},
+            }
+        }
+        subsets
+    }
+
+    // Estimated errors of the subtree on the records: each leaf's observed errors plus the
+    // pessimistic correction for them
+    fn estimated_errors<T: Record>(vertex: &DecisionVertex, records: &Vec<(&T, f64)>, confidence: f64) -> f64 {
+        match *vertex {
+            Leaf(ref label, _) => {
+                let total = records.iter().fold(0f64, |sum, &(_, weight)| sum + weight);
+                let errors = records.iter()
+                    .filter(|&&(record, _)| !eq_slice(record.get_label(), label.as_slice()))
+                    .fold(0f64, |sum, &(_, weight)| sum + weight);
+                errors + added_errors(total, errors, confidence)
+            },
+            _ => {
+                let subsets = route(vertex, records);
+                vertex.children().iter().zip(subsets.iter())
+                    .fold(0f64, |sum, (child, subset)| sum + estimated_errors(*child, subset, confidence))
+            },
+        }
+    }
+
+    // Extra errors to add to `errors` observed out of `total` so that their rate is the upper
+    // limit of the binomial confidence interval (Quinlan's U_CF), as computed by C4.5
+    fn added_errors(total: f64, errors: f64, confidence: f64) -> f64 {
+        if total <= 0f64 {
+            return 0f64;
+        }
+
+        if errors < 1f64 {
+            // Exact limit for no errors, interpolated up to one error
+            let base = total * (1f64 - confidence.powf(1f64 / total));
+            if errors == 0f64 {
+                return base;
+            }
+            return base + errors * (added_errors(total, 1f64, confidence) - base);
+        }
+
+        // C4.5's AddErrs allows two thirds of the correct cases when nearly every case is an error
+        if errors + 0.5f64 >= total {
+            return 0.67f64 * (total - errors).max(0f64);
+        }
+
+        // Normal approximation to the binomial
+        let z = normal_deviate(confidence);
+        let rate = (errors + 0.5f64) / total;
+        let upper = (rate + z * z / (2f64 * total)
+            + z * (rate / total - rate * rate / total + z * z / (4f64 * total * total)).sqrt())
+            / (1f64 + z * z / total);
+        upper * total - errors
+    }
+
+    // Standard normal deviate with upper tail probability `p` in (0, 0.5] (Abramowitz and Stegun
+    // 26.2.23, accurate to 4.5e-4)
+    fn normal_deviate(p: f64) -> f64 {
+        let t = (-2f64 * p.ln()).sqrt();
+        t - (2.515517f64 + 0.802853f64 * t + 0.010328f64 * t * t)
+            / (1f64 + 1.432788f64 * t + 0.189269f64 * t * t + 0.001308f64 * t * t * t)
+    }
+
+    // Index in `children` of the child with the most training samples (first wins)
+    fn largest_child(vertex: &DecisionVertex) -> uint {
+        let mut largest = 0u;
+        let mut most = 0u;
+        for (index, child) in vertex.children().iter().enumerate() {
+            if child.stats().samples > most {
+                largest = index;
+                most = child.stats().samples;
+            }
+        }
+        largest
+    }
+
+    // Recomputes the stats of every vertex of the subtree from the weighted records now reaching
+    // it, relabeling leaves any record reaches with their majority class
+    fn restate<T: Record>(vertex: &mut DecisionVertex, records: &Vec<(&T, f64)>) {
+        // Route with the old stats, which decide how records are divided between children
+        let subsets = match *vertex {
+            Leaf(..) => vec![],
+            _ => route(vertex, records),
+        };
+
+        let mut weights: TreeMap<String, f64> = TreeMap::new();
+        for &(record, weight) in records.iter() {
+            let label = record.get_label().to_string();
+            let total = weights.find(&label).map_or(0f64, |x| *x) + weight;
+            weights.insert(label, total);
+        }
+        let class_counts = weights.into_iter()
+            .map(|(label, weight)| (label, weight.round() as uint))
+            .filter(|&(_, count)| count > 0)
+            .collect();
+        let stats = NodeStats::with_measure(class_counts, vertex.measure());
+
+        let leaf = match *vertex {
+            Branch(_, _, _, ref mut old) => { *old = stats.clone(); false },
+            Threshold(_, _, _, _, _, ref mut old) => { *old = stats.clone(); false },
+            Leaf(..) => true,
+        };
+        if leaf && stats.samples > 0 {
+            *vertex = majority_leaf(&stats);
+        }
+        for (child, subset) in vertex.children_mut().into_iter().zip(subsets.iter()) {
+            restate(child, subset);
+        }
+    }
+
+    #[doc = "
+    Minimal cost-complexity (CART) pruning sequence
+
+    Returns the nested subtrees obtained by repeatedly collapsing the weakest links of the tree,
+    together with the increasing complexity parameters at which each becomes optimal. The first
+    step is the tree with every split that does not reduce training error collapsed, at alpha 0;
+    the last is the root alone. Errors are training misclassifications from the vertex stats, as
+    a fraction of the root's samples.
+    "]
+    pub fn cost_complexity_path(tree: &DecisionVertex) -> Vec<PruningStep> {
+        let samples = root_samples(tree);
+        let mut current = tree.clone();
+        collapse_weak_links(&mut current, 0f64, samples);
+        let mut steps = vec![PruningStep { alpha: 0f64, tree: current.clone() }];
+
+        loop {
+            match weakest_link(&current, samples) {
+                Some(alpha) => {
+                    collapse_weak_links(&mut current, alpha, samples);
+                    steps.push(PruningStep { alpha: alpha, tree: current.clone() });
+                },
+                None => break,
+            }
+        }
+
+        steps
+    }
+
+    #[doc = "
+    Cost-complexity pruning
+
+    Prunes the tree to the subtree of its `cost_complexity_path` that is optimal for `alpha`,
+    collapsing every link no stronger than `alpha`.
+    "]
+    pub fn cost_complexity(tree: &mut DecisionVertex, alpha: f64) -> PruneReport {
+        let mut report = PruneReport::new(tree);
+        let samples = root_samples(tree);
+        report.subtrees_pruned = collapse_weak_links(tree, 0f64, samples);
+
+        loop {
+            match weakest_link(tree, samples) {
+                Some(weakest) if weakest <= alpha + ALPHA_EPSILON => {
+                    report.subtrees_pruned += collapse_weak_links(tree, weakest, samples);
+                },
+                _ => break,
+            }
+        }
+
+        report.nodes_after = tree.node_count();
+        report
+    }
+
+    #[doc = "
+    Picks the cost-complexity parameter by cross-validation
+
+    Grows a tree from all `records` with `grow` and takes the alphas of its pruning sequence.
+    Then, for each of `folds` folds (record `i` is held out in fold `i % folds`), grows a tree
+    from the rest, prunes it at the geometric mean of each alpha and the next, and counts the
+    held-out records it misclassifies. Returns the alpha with the fewest errors over all folds,
+    ties going to the larger alpha.
+
+    Fails with `InvalidFolds` unless there are at least 2 folds and no more folds than records.
+    "]
+    pub fn select_alpha<'a, T: Record>(records: &'a [T], folds: uint, grow: |Vec<&'a T>| -> DecisionVertex) -> Result<f64, PruneError> {
+        if folds < 2 || folds > records.len() {
+            return Err(InvalidFolds(folds));
+        }
+
+        let alphas: Vec<f64> = cost_complexity_path(&grow(records.iter().collect()))
+            .iter().map(|x| x.alpha).collect();
+        let betas: Vec<f64> = range(0, alphas.len()).map(|k| {
+            if k + 1 < alphas.len() { (alphas[k] * alphas[k + 1]).sqrt() } else { alphas[k] }
+        }).collect();
+
+        let mut fold_errors = Vec::from_elem(alphas.len(), 0u);
+        for fold in range(0, folds) {
+            let mut training = Vec::new();
+            let mut held_out = Vec::new();
+            for (i, record) in records.iter().enumerate() {
+                if i % folds == fold { held_out.push(record) } else { training.push(record) }
+            }
+
+            let path = cost_complexity_path(&grow(training));
+            for (k, beta) in betas.iter().enumerate() {
+                match path.iter().take_while(|x| x.alpha <= *beta + ALPHA_EPSILON).last() {
+                    Some(step) => *fold_errors.get_mut(k) += errors(&step.tree, &held_out),
+                    None => {},
+                }
+            }
+        }
+
+        let mut best = 0u;
+        for k in range(1, alphas.len()) {
+            if fold_errors[k] <= fold_errors[best] {
+                best = k;
+            }
+        }
+        Ok(alphas[best])
+    }
+
+    // Samples at the root, at least one so error rates stay finite
+    fn root_samples(tree: &DecisionVertex) -> f64 {
+        if tree.stats().samples > 0 { tree.stats().samples as f64 } else { 1f64 }
+    }
+
+    // Training records a majority leaf at the vertex would misclassify
+    fn node_errors(stats: &NodeStats) -> uint {
+        stats.samples - stats.class_counts.values().fold(0u, |most, x| if *x > most { *x } else { most })
+    }
+
+    // Training records the subtree misclassifies, and its number of leaves
+    fn subtree_errors(vertex: &DecisionVertex) -> (uint, uint) {
+        match *vertex {
+            Leaf(ref label, ref stats) => {
+                (stats.samples - stats.class_counts.find(label).map_or(0u, |x| *x), 1u)
+            },
+            _ => vertex.children().iter().fold((0u, 0u), |(errors, leaves), x| {
+                let (child_errors, child_leaves) = subtree_errors(*x);
+                (errors + child_errors, leaves + child_leaves)
+            }),
+        }
+    }
+
+    // Increase in error rate per leaf removed by collapsing the vertex. A vertex over a single
+    // leaf removes no leaves and is always among the weakest links.
+    fn link_strength(vertex: &DecisionVertex, samples: f64) -> f64 {
+        let (errors, leaves) = subtree_errors(vertex);
+        if leaves <= 1 {
+            return 0f64;
+        }
+        (node_errors(vertex.stats()) as f64 - errors as f64) / samples / (leaves - 1) as f64
+    }
+
+    // Strength of the weakest link in the tree, none for a leaf
+    fn weakest_link(vertex: &DecisionVertex, samples: f64) -> Option<f64> {
+        match *vertex {
+            Leaf(..) => None,
+            _ => vertex.children().iter().fold(Some(link_strength(vertex, samples)), |weakest, x| {
+                match (weakest, weakest_link(*x, samples)) {
+                    (Some(a), Some(b)) => Some(a.min(b)),
+                    (a, None) => a,
+                    (None, b) => b,
+                }
+            }),
+        }
+    }
+
+    // Collapses every subtree whose link is no stronger than `alpha` into a majority leaf,
+    // returning how many were collapsed
+    fn collapse_weak_links(vertex: &mut DecisionVertex, alpha: f64, samples: f64) -> uint {
+        match *vertex {
+            Leaf(..) => return 0,
+            _ => {},
+        }
+
+        if link_strength(vertex, samples) <= alpha + ALPHA_EPSILON {
+            let leaf = majority_leaf(vertex.stats());
+            *vertex = leaf;
+            return 1;
+        }
+
+        vertex.children_mut().into_iter().fold(0u, |sum, x| sum + collapse_weak_links(x, alpha, samples))
+    }
+
+    // Leaf standing in for a vertex, labeled with the majority class of its training examples
+    // (ties going to the lexicographically smallest class)
+    fn majority_leaf(stats: &NodeStats) -> DecisionVertex {
+        let mut best_class = String::new();
+        let mut best_count = 0u;
+        for (class, count) in stats.class_counts.iter() {
+            if *count > best_count {
+                best_class = class.clone();
+                best_count = *count;
+            }
+        }
+
+        Leaf(best_class, stats.clone())
+    }
+
+    // outlook? with a wind? split under rain that only fits noise
+    #[cfg(test)]
+    fn overfit_tree() -> DecisionVertex {
+        let mut wind_children = TreeMap::new();
+        wind_children.insert("strong".to_string(), Leaf("n".to_string(), counts(vec![("n", 1)])));
+        wind_children.insert("weak".to_string(), Leaf("y".to_string(), counts(vec![("y", 4)])));
+        let mut children = TreeMap::new();
+        children.insert("sunny".to_string(), Leaf("n".to_string(), counts(vec![("n", 3)])));
+        children.insert("rain".to_string(), Branch("wind".to_string(), wind_children, None, counts(vec![("n", 1), ("y", 4)])));
+        Branch("outlook".to_string(), children, None, counts(vec![("n", 4), ("y", 4)]))
+    }
+
+    #[test]
+    fn reduced_error_prunes_noise() {
+        let mut tree = overfit_tree();
+        let validation = vec![
+            record(vec![("outlook", "sunny"), ("wind", "weak"), ("class", "n")]),
+            record(vec![("outlook", "rain"), ("wind", "strong"), ("class", "y")]),
+            record(vec![("outlook", "rain"), ("wind", "weak"), ("class", "y")])];
+
+        let report = reduced_error(&mut tree, validation.as_slice());
+
+        assert_eq!(PruneReport { nodes_before: 5, nodes_after: 3, subtrees_pruned: 1, subtrees_raised: 0 }, report);
+        assert_eq!(2, report.nodes_removed());
+        match tree {
+            Branch(_, ref children, _, _) => match children.find(&"rain".to_string()) {
+                Some(&Leaf(ref label, _)) => assert_eq!("y".to_string(), *label),
+                _ => fail!("expected a leaf"),
+            },
+            _ => fail!("expected a branch"),
+        }
+    }
+
+    #[test]
+    fn reduced_error_keeps_useful_splits() {
+        let mut tree = overfit_tree();
+        let validation = vec![
+            record(vec![("outlook", "sunny"), ("wind", "weak"), ("class", "n")]),
+            record(vec![("outlook", "rain"), ("wind", "strong"), ("class", "n")]),
+            record(vec![("outlook", "rain"), ("wind", "weak"), ("class", "y")])];
+
+        let report = reduced_error(&mut tree, validation.as_slice());
+
+        assert_eq!(0, report.nodes_removed());
+    }
+
+    #[test]
+    fn added_errors_matches_c45() {
+        // Quinlan's U_25% for leaves with no errors
+        assert!((added_errors(6f64, 0f64, 0.25f64) - 1.238f64).abs() < 1e-3);
+        assert!((added_errors(1f64, 0f64, 0.25f64) - 0.75f64).abs() < 1e-9);
+        assert!((added_errors(16f64, 1f64, 0.25f64) - 1.476f64).abs() < 1e-2);
+        assert_eq!(0f64, added_errors(0f64, 0f64, 0.25f64));
+        // Nearly all errors
+        assert_eq!(0f64, added_errors(2f64, 2f64, 0.25f64));
+        assert!((added_errors(2f64, 1.75f64, 0.25f64) - 0.1675f64).abs() < 1e-9);
+    }
+
+    // Quinlan's example: three pure leaves of 6, 9 and 1 records estimate 3.27 errors, a leaf
+    // for all 16 with one error only 2.48
+    #[test]
+    fn pessimistic_replaces_subtree() {
+        let mut children = TreeMap::new();
+        children.insert("a".to_string(), Leaf("y".to_string(), counts(vec![("y", 6)])));
+        children.insert("b".to_string(), Leaf("y".to_string(), counts(vec![("y", 9)])));
+        children.insert("c".to_string(), Leaf("n".to_string(), counts(vec![("n", 1)])));
+        let mut tree = Branch("x".to_string(), children, None, counts(vec![("n", 1), ("y", 15)]));
+        let mut training = Vec::new();
+        for _ in range(0u, 6) { training.push(record(vec![("x", "a"), ("class", "y")])); }
+        for _ in range(0u, 9) { training.push(record(vec![("x", "b"), ("class", "y")])); }
+        training.push(record(vec![("x", "c"), ("class", "n")]));
+
+        let report = pessimistic(&mut tree, training.as_slice(), 0.25f64, false).unwrap();
+
+        assert_eq!(PruneReport { nodes_before: 4, nodes_after: 1, subtrees_pruned: 1, subtrees_raised: 0 }, report);
+        match tree {
+            Leaf(ref label, ref stats) => {
+                assert_eq!("y".to_string(), *label);
+                assert_eq!(16, stats.samples);
+            },
+            _ => fail!("expected a leaf"),
+        }
+        assert_eq!(Err(InvalidConfidence(0.75f64)), pessimistic(&mut tree, training.as_slice(), 0.75f64, false));
+    }
+
+    #[test]
+    fn pessimistic_raises_largest_child() {
+        let mut b_children = TreeMap::new();
+        b_children.insert("u".to_string(), Leaf("y".to_string(), counts(vec![("y", 10)])));
+        b_children.insert("v".to_string(), Leaf("n".to_string(), counts(vec![("n", 10)])));
+        let mut children = TreeMap::new();
+        children.insert("p".to_string(), Branch("b".to_string(), b_children, None, counts(vec![("n", 10), ("y", 10)])));
+        children.insert("q".to_string(), Leaf("n".to_string(), counts(vec![("n", 2)])));
+        let tree = Branch("a".to_string(), children, None, counts(vec![("n", 12), ("y", 10)]));
+        let mut training = Vec::new();
+        for _ in range(0u, 10) { training.push(record(vec![("a", "p"), ("b", "u"), ("class", "y")])); }
+        for _ in range(0u, 10) { training.push(record(vec![("a", "p"), ("b", "v"), ("class", "n")])); }
+        for _ in range(0u, 2) { training.push(record(vec![("a", "q"), ("b", "v"), ("class", "n")])); }
+
+        let mut unraised = tree.clone();
+        assert_eq!(0, pessimistic(&mut unraised, training.as_slice(), 0.25f64, false).unwrap().nodes_removed());
+
+        let mut raised = tree.clone();
+        let report = pessimistic(&mut raised, training.as_slice(), 0.25f64, true).unwrap();
+
+        assert_eq!(PruneReport { nodes_before: 5, nodes_after: 3, subtrees_pruned: 0, subtrees_raised: 1 }, report);
+        match raised {
+            Branch(ref attr, ref children, _, ref stats) => {
+                assert_eq!("b".to_string(), *attr);
+                assert_eq!(22, stats.samples);
+                // The records of the removed q leaf now reach v
+                assert_eq!(counts(vec![("y", 10)]), *children.find(&"u".to_string()).unwrap().stats());
+                assert_eq!(counts(vec![("n", 12)]), *children.find(&"v".to_string()).unwrap().stats());
+            },
+            _ => fail!("expected a branch"),
+        }
+    }
+
+    #[test]
+    fn cost_complexity_path_weakest_links() {
+        let path = cost_complexity_path(&overfit_tree());
+
+        let alphas: Vec<f64> = path.iter().map(|x| x.alpha).collect();
+        let nodes: Vec<uint> = path.iter().map(|x| x.tree.node_count()).collect();
+        assert_eq!(vec![0f64, 0.125f64, 0.375f64], alphas);
+        assert_eq!(vec![5u, 3, 1], nodes);
+    }
+
+    #[test]
+    fn cost_complexity_prunes_to_alpha() {
+        let mut tree = overfit_tree();
+
+        let report = cost_complexity(&mut tree, 0.2f64);
+
+        assert_eq!(PruneReport { nodes_before: 5, nodes_after: 3, subtrees_pruned: 1, subtrees_raised: 0 }, report);
+        assert_eq!(2, tree.leaf_count());
+    }
+
+    #[test]
+    fn cost_complexity_single_child() {
+        // What id3 grows on an attribute with one value
+        let mut children = TreeMap::new();
+        children.insert("0".to_string(), Leaf("y".to_string(), counts(vec![("n", 1), ("y", 2)])));
+        let tree = Branch("a".to_string(), children, None, counts(vec![("n", 1), ("y", 2)]));
+
+        let path = cost_complexity_path(&tree);
+
+        assert_eq!(1, path.len());
+        assert_eq!(0f64, path[0].alpha);
+        assert_eq!(1, path[0].tree.node_count());
+        let mut pruned = tree.clone();
+        assert_eq!(1, cost_complexity(&mut pruned, 0f64).subtrees_pruned);
+    }
+
+    #[test]
+    fn select_alpha_cross_validation() {
+        let records = vec![
+            record(vec![("outlook", "sunny"), ("wind", "weak"), ("class", "n")]),
+            record(vec![("outlook", "sunny"), ("wind", "strong"), ("class", "n")]),
+            record(vec![("outlook", "rain"), ("wind", "weak"), ("class", "y")]),
+            record(vec![("outlook", "rain"), ("wind", "strong"), ("class", "y")]),
+            record(vec![("outlook", "sunny"), ("wind", "weak"), ("class", "n")]),
+            record(vec![("outlook", "rain"), ("wind", "weak"), ("class", "y")]),
+            record(vec![("outlook", "rain"), ("wind", "strong"), ("class", "n")]),
+            record(vec![("outlook", "sunny"), ("wind", "strong"), ("class", "n")])];
+
+        // The full tree's alphas are 0 and 0.375. Pruned at 0, the fold trees misclassify one
+        // held-out record each, while the second fold's tree pruned to its root misclassifies two.
+        let alpha = select_alpha(records.as_slice(), 2, |x| id3(x, "class", vec!["outlook", "wind"], 0f64));
+        assert_eq!(Ok(0f64), alpha);
+
+        let too_few = select_alpha(records.as_slice(), 1, |x| id3(x, "class", vec!["outlook", "wind"], 0f64));
+        assert_eq!(Err(InvalidFolds(1)), too_few);
+        let too_many = select_alpha(records.as_slice(), 9, |x| id3(x, "class", vec!["outlook", "wind"], 0f64));
+        assert_eq!(Err(InvalidFolds(9)), too_many);
+    }
+}
+
+#[experimental]
+#[doc = "Reading examples from delimited text files"]
+pub mod table {
+    use std::fmt;
+    use std::io::{BufferedReader, File, IoError, IoResult, InvalidInput};
+    use std::str::eq_slice;
+    use tree::Record;
+    use id3::MissingValueConfig;
+    #[cfg(test)]
+    use std::io::BufReader;
+
+    #[doc = "
+    Layout of a delimited text file holding one example per line: the name of each column in
+    file order, which column is the label, the delimiter, the missing-value marker, any columns
+    to leave out of the attributes and the values declared for each column. A field may be
+    enclosed in double quotes to hold the delimiter, a doubled quote inside standing for one quote.
+    "]
+    #[deriving(Clone, Show)]
+    pub struct Schema {
+        columns: Vec<String>,
+        label: uint,
+        delimiter: char,
+        missing_marker: String,
+        ignored: Vec<uint>,
+        domains: Vec<Option<Vec<String>>>,
+        numeric: Vec<uint>,
+    }
+
+    impl Schema {
+        #[doc = "Comma-delimited schema with `?` marking missing values and no ignored columns."]
+        pub fn new(columns: Vec<&str>, label_attribute_name: &str) -> Schema {
+            let label = match columns.iter().position(|x| eq_slice(*x, label_attribute_name)) {
+                Some(label) => label,
+                None => fail!("no column named {}", label_attribute_name),
+            };
+            Schema {
+                columns: columns.iter().map(|x| x.to_string()).collect(),
+                label: label,
+                delimiter: ',',
+                missing_marker: "?".to_string(),
+                ignored: vec![],
+                domains: Vec::from_elem(columns.len(), None),
+                numeric: vec![],
+            }
+        }
+
+        #[doc = "
+        Character separating fields. With a whitespace delimiter any run of whitespace separates
+        fields and leading or trailing whitespace is skipped.
+        "]
+        pub fn delimiter(mut self, delimiter: char) -> Schema {
+            self.delimiter = delimiter;
+            self
+        }
+
+        #[doc = "Value standing for a missing attribute. Defaults to `?`."]
+        pub fn missing_marker(mut self, marker: &str) -> Schema {
+            self.missing_marker = marker.to_string();
+            self
+        }
+
+        #[doc = "Columns that are read but not offered as attributes, such as row identifiers."]
+        pub fn ignore(mut self, columns: Vec<&str>) -> Schema {
+            for name in columns.iter() {
+                match self.column_index(*name) {
+                    Some(index) => if !self.ignored.contains(&index) { self.ignored.push(index) },
+                    None => fail!("no column named {}", name),
+                }
+            }
+            self
+        }
+
+        #[doc = "Declares the values a column may take."]
+        pub fn domain(mut self, column: &str, values: Vec<&str>) -> Schema {
+            match self.column_index(column) {
+                Some(index) => *self.domains.get_mut(index) = Some(values.iter().map(|x| x.to_string()).collect()),
+                None => fail!("no column named {}", column),
+            }
+            self
+        }
+
+        #[doc = "Columns holding numbers, whose values must parse as finite numbers unless missing."]
+        pub fn numeric(mut self, columns: Vec<&str>) -> Schema {
+            for name in columns.iter() {
+                match self.column_index(*name) {
+                    Some(index) => if !self.numeric.contains(&index) { self.numeric.push(index) },
+                    None => fail!("no column named {}", name),
+                }
+            }
+            self
+        }
+
+        #[doc = "Values declared for a column, if any were."]
+        pub fn declared_domain(&self, column: &str) -> Option<&[String]> {
+            match self.column_index(column) {
+                Some(index) => self.domains[index].as_ref().map(|x| x.as_slice()),
+                None => None,
+            }
+        }
+
+        #[doc = "Names of all columns in file order."]
+        pub fn columns(&self) -> Vec<&str> {
+            self.columns.iter().map(|x| x.as_slice()).collect()
+        }
+
+        #[doc = "Position of the column with the given name."]
+        pub fn column_index(&self, name: &str) -> Option<uint> {
+            self.columns.iter().position(|x| eq_slice(x.as_slice(), name))
+        }
+
+        pub fn label_attribute_name(&self) -> &str {
+            self.columns[self.label].as_slice()
+        }
+
+        #[doc = "Names of the columns other than the label and the ignored ones, in file order."]
+        pub fn attribute_names(&self) -> Vec<&str> {
+            range(0, self.columns.len())
+                .filter(|x| *x != self.label && !self.ignored.contains(x))
+                .map(|x| self.columns[x].as_slice())
+                .collect()
+        }
+
+        pub fn marker(&self) -> &str {
+            self.missing_marker.as_slice()
+        }
+
+        #[doc = "Missing-value configuration using this schema's marker, for `TreeBuilder::missing_values`."]
+        pub fn missing_values(&self) -> MissingValueConfig {
+            MissingValueConfig::new(self.missing_marker.as_slice())
+        }
+
+        #[doc = "
+        Reads one record per non-empty line. Fails with `InvalidInput` on the first line whose
+        number of fields differs from the number of columns.
+        "]
+        pub fn read<B: Buffer>(&self, reader: &mut B) -> IoResult<Vec<TableRecord>> {
+            let mut records = Vec::new();
+            for (number, fields) in try!(self.lines(reader)).into_iter() {
+                if fields.len() != self.columns.len() {
+                    return Err(IoError {
+                        kind: InvalidInput,
+                        desc: "wrong number of fields",
+                        detail: Some(format!("line {}: expected {} fields, found {}", number, self.columns.len(), fields.len())),
+                    });
+                }
+                records.push(TableRecord::new(self, fields));
+            }
+            Ok(records)
+        }
+
+        #[doc = "Reads the records of the file at `path`; see `read`."]
+        pub fn read_file(&self, path: &Path) -> IoResult<Vec<TableRecord>> {
+            let mut reader = BufferedReader::new(try!(File::open(path)));
+            self.read(&mut reader)
+        }
+
+        #[doc = "
+        Reads one record per non-empty line, checking each line's number of fields, that its
+        values belong to their columns' declared domains, that values of numeric columns are
+        numbers and that its label is not missing. Missing attribute values and ignored columns
+        are not checked. Lines with problems are left out of the records with `DropInvalid`; with
+        `RejectInvalid` any problem fails the read with `InvalidInput`.
+        "]
+        pub fn read_valid<B: Buffer>(&self, reader: &mut B, invalid: InvalidRows) -> IoResult<(Vec<TableRecord>, ValidationReport)> {
+            let rows = try!(self.lines(reader));
+            self.keep_valid(rows, invalid)
+        }
+
+        #[doc = "Reads the records of the file at `path`; see `read_valid`."]
+        pub fn read_file_valid(&self, path: &Path, invalid: InvalidRows) -> IoResult<(Vec<TableRecord>, ValidationReport)> {
+            let mut reader = BufferedReader::new(try!(File::open(path)));
+            self.read_valid(&mut reader, invalid)
+        }
+
+        #[doc = "
+        Checks rows read some other way, each given as its line number and fields, as
+        `read_valid` does, leaving out or rejecting those with problems.
+        "]
+        pub fn keep_valid(&self, rows: Vec<(uint, Vec<String>)>, invalid: InvalidRows) -> IoResult<(Vec<TableRecord>, ValidationReport)> {
+            let mut records = Vec::new();
+            let mut report = ValidationReport::new();
+            for (number, fields) in rows.into_iter() {
+                let problems = self.problems(fields.as_slice());
+                if report.add(number, problems) {
+                    records.push(TableRecord::new(self, fields));
+                }
+            }
+
+            match invalid {
+                RejectInvalid if !report.is_valid() => Err(IoError {
+                    kind: InvalidInput,
+                    desc: "invalid rows",
+                    detail: Some(format!("{} of {} rows invalid, first {}", report.invalid_rows, report.rows, report.issues[0])),
+                }),
+                _ => Ok((records, report)),
+            }
+        }
+
+        #[doc = "
+        Checks records read some other way against the declared domains, as `read_valid` does.
+        Rows are numbered by their position in `records`, counting from 1.
+        "]
+        pub fn validate(&self, records: &[TableRecord]) -> ValidationReport {
+            let mut report = ValidationReport::new();
+            for (index, record) in records.iter().enumerate() {
+                report.add(index + 1, self.problems(record.fields()));
+            }
+            report
+        }
+
+        // Non-empty lines, split into fields, with their line numbers counting from 1
+        fn lines<B: Buffer>(&self, reader: &mut B) -> IoResult<Vec<(uint, Vec<String>)>> {
+            let mut lines = Vec::new();
+            for (number, line) in reader.lines().enumerate() {
+                let line = try!(line);
+                let line = line.as_slice().trim_right_chars(['\r', '\n'].as_slice());
+                if line.trim().is_empty() { continue; }
+                lines.push((number + 1, self.split(line)));
+            }
+            Ok(lines)
+        }
+
+        // What is wrong with a row's fields
+        fn problems(&self, fields: &[String]) -> Vec<Problem> {
+            if fields.len() != self.columns.len() {
+                return vec![WrongArity(self.columns.len(), fields.len())];
+            }
+            range(0, fields.len()).filter_map(|x| {
+                if fields[x] == self.missing_marker {
+                    return if x == self.label { Some(MissingLabel) } else { None };
+                }
+                if self.ignored.contains(&x) {
+                    return None;
+                }
+                match self.domains[x] {
+                    Some(ref values) if !values.contains(&fields[x]) => Some(UnexpectedValue(self.columns[x].clone(), fields[x].clone())),
+                    _ if self.numeric.contains(&x) && !is_number(fields[x].as_slice()) => {
+                        Some(NotANumber(self.columns[x].clone(), fields[x].clone()))
+                    },
+                    _ => None,
+                }
+            }).collect()
+        }
+
+        // Fields of one line, trimmed
+        fn split(&self, line: &str) -> Vec<String> {
+            // Runs of whitespace count as one delimiter
+            let whitespace = self.delimiter.is_whitespace();
+            let mut fields = Vec::new();
+            let mut field = String::new();
+            let (mut in_quotes, mut quoted) = (false, false);
+            let mut chars = line.trim().chars().peekable();
+            loop {
+                match chars.next() {
+                    Some('"') if in_quotes => {
+                        if chars.peek() == Some(&'"') {
+                            chars.next();
+                            field.push('"');
+                        } else {
+                            in_quotes = false;
+                        }
+                    },
+                    Some('"') if !quoted && field.as_slice().trim().is_empty() => {
+                        field.clear();
+                        in_quotes = true;
+                        quoted = true;
+                    },
+                    Some(c) if !in_quotes && (c == self.delimiter || whitespace && c.is_whitespace()) => {
+                        if whitespace && !quoted && field.is_empty() { continue; }
+                        fields.push(if quoted { field } else { field.as_slice().trim().to_string() });
+                        field = String::new();
+                        quoted = false;
+                    },
+                    // Spaces between a closing quote and the delimiter
+                    Some(c) if quoted && !in_quotes && c.is_whitespace() => {},
+                    Some(c) => field.push(c),
+                    None => {
+                        fields.push(if quoted { field } else { field.as_slice().trim().to_string() });
+                        return fields;
+                    },
+                }
+            }
+        }
+    }
+
+    // Whether a value reads as a finite number, as numeric attributes are read for training
+    fn is_number(value: &str) -> bool {
+        from_str::<f64>(value.trim()).map_or(false, |x| x.is_finite())
+    }
+
+    #[doc = "What to do with rows that do not fit a `Schema`."]
+    #[deriving(Clone, PartialEq, Show)]
+    pub enum InvalidRows {
+        /// Leave them out of the records and report them.
+        DropInvalid,
+        /// Fail the read.
+        RejectInvalid,
+    }
+
+    #[doc = "Something wrong with a row."]
+    #[deriving(Clone, PartialEq, Show)]
+    pub enum Problem {
+        /// A value outside the declared domain: the column's name and the value.
+        UnexpectedValue(String, String),
+        /// The wrong number of fields: the number expected and the number found.
+        WrongArity(uint, uint),
+        /// A value of a numeric column that is not a finite number: the column's name and the value.
+        NotANumber(String, String),
+        /// The missing-value marker in the label column.
+        MissingLabel,
+    }
+
+    #[doc = "A problem found in a row."]
+    #[deriving(Clone, PartialEq)]
+    pub struct Issue {
+        /// Line of the row counting from 1, or its position for `Schema::validate`.
+        pub row: uint,
+        pub problem: Problem,
+    }
+
+    impl fmt::Show for Issue {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self.problem {
+                UnexpectedValue(ref column, ref value) => write!(f, "row {}: unexpected value {} for {}", self.row, value, column),
+                WrongArity(expected, found) => write!(f, "row {}: expected {} fields, found {}", self.row, expected, found),
+                NotANumber(ref column, ref value) => write!(f, "row {}: {} is not a number for {}", self.row, value, column),
+                MissingLabel => write!(f, "row {}: missing label", self.row),
+            }
+        }
+    }
+
+    #[doc = "The problems found by validating rows against a `Schema`."]
+    #[deriving(Clone, PartialEq, Show)]
+    pub struct ValidationReport {
+        /// Rows checked.
+        pub rows: uint,
+        /// Rows with at least one problem.
+        pub invalid_rows: uint,
+        /// Every problem, in row order.
+        pub issues: Vec<Issue>,
+    }
+
+    impl ValidationReport {
+        fn new() -> ValidationReport {
+            ValidationReport { rows: 0, invalid_rows: 0, issues: vec![] }
+        }
+
+        #[doc = "Whether every row fits the schema."]
+        pub fn is_valid(&self) -> bool {
+            self.issues.is_empty()
+        }
+
+        // Records a row's problems, returning whether it had none
+        fn add(&mut self, row: uint, problems: Vec<Problem>) -> bool {
+            self.rows += 1;
+            if problems.is_empty() {
+                return true;
+            }
+            self.invalid_rows += 1;
+            self.issues.extend(problems.into_iter().map(|x| Issue { row: row, problem: x }));
+            false
+        }
+    }
+
+    #[doc = "One line of a delimited file, read as a `Record` through its `Schema`."]
+    #[deriving(Clone, Show)]
+    pub struct TableRecord<'a> {
+        schema: &'a Schema,
+        fields: Vec<String>,
+    }
+
+    impl<'a> TableRecord<'a> {
+        #[doc = "Record holding one field per column of the schema."]
+        pub fn new(schema: &'a Schema, fields: Vec<String>) -> TableRecord<'a> {
+            TableRecord { schema: schema, fields: fields }
+        }
+
+        pub fn schema(&self) -> &'a Schema {
+            self.schema
+        }
+
+        #[doc = "The fields in file order."]
+        pub fn fields(&self) -> &[String] {
+            self.fields.as_slice()
+        }
+    }
+
+    impl<'a> Record for TableRecord<'a> {
+        fn get_attribute(&self, attr_name: &str) -> &str {
+            match self.schema.column_index(attr_name) {
+                Some(index) => self.fields[index].as_slice(),
+                None => fail!("no attribute named {}", attr_name),
+            }
+        }
+
+        fn get_attribute_names(&self) -> Vec<&str> {
+            self.schema.attribute_names()
+        }
+
+        fn get_label(&self) -> &str {
+            self.fields[self.schema.label].as_slice()
+        }
+    }
+
+    #[test]
+    fn schema_attribute_names() {
+        let schema = Schema::new(vec!["class", "a1", "a2", "id"], "class").ignore(vec!["id"]);
+
+        assert_eq!(vec!["a1", "a2"], schema.attribute_names());
+        assert_eq!(vec!["class", "a1", "a2", "id"], schema.columns());
+        assert_eq!("class", schema.label_attribute_name());
+        assert_eq!(Some(3), schema.column_index("id"));
+        assert_eq!("?", schema.missing_values().marker.as_slice());
+
+        let schema = schema.domain("a1", vec!["y", "n"]);
+        assert_eq!(Some(["y".to_string(), "n".to_string()].as_slice()), schema.declared_domain("a1"));
+        assert_eq!(None, schema.declared_domain("a2"));
+    }
+
+    #[test]
+    fn schema_reads_records() {
+        let schema = Schema::new(vec!["outlook", "wind", "class"], "class");
+        let records = schema.read(&mut BufReader::new(b"sunny,weak,n\n\nrain, strong ,y\r\n")).unwrap();
+
+        assert_eq!(2, records.len());
+        assert_eq!("strong", records[1].get_attribute("wind"));
+        assert_eq!("y", records[1].get_label());
+        assert_eq!(vec!["outlook", "wind"], records[0].get_attribute_names());
+
+        // Monk's problems files pad fields with runs of spaces
+        let schema = Schema::new(vec!["class", "a1", "id"], "class").delimiter(' ').ignore(vec!["id"]);
+        let records = schema.read(&mut BufReader::new(b" 1 3  data_5\n")).unwrap();
+        assert_eq!(vec!["1".to_string(), "3".to_string(), "data_5".to_string()], records[0].fields().to_vec());
+    }
+
+    #[test]
+    fn schema_reads_quoted_fields() {
+        let schema = Schema::new(vec!["name", "note", "class"], "class");
+        let records = schema.read(&mut BufReader::new(b"\"Smith, J.\", \" said \"\"hi\"\"\" ,y\n,,n\n")).unwrap();
+
+        assert_eq!(vec!["Smith, J.".to_string(), " said \"hi\"".to_string(), "y".to_string()], records[0].fields().to_vec());
+        assert_eq!(vec![String::new(), String::new(), "n".to_string()], records[1].fields().to_vec());
+
+        let schema = Schema::new(vec!["name", "class"], "class").delimiter(' ');
+        let records = schema.read(&mut BufReader::new(b"\"a  b\"   y\n")).unwrap();
+        assert_eq!(vec!["a  b".to_string(), "y".to_string()], records[0].fields().to_vec());
+    }
+
+    #[test]
+    fn schema_rejects_wrong_arity() {
+        let schema = Schema::new(vec!["outlook", "class"], "class");
+
+        match schema.read(&mut BufReader::new(b"sunny,n\nrain\n")) {
+            Err(error) => {
+                assert_eq!(InvalidInput, error.kind);
+                assert_eq!(Some("line 2: expected 2 fields, found 1".to_string()), error.detail);
+            },
+            Ok(_) => fail!("expected an error"),
+        }
+    }
+
+    #[test]
+    fn schema_validates_rows() {
+        let schema = Schema::new(vec!["outlook", "id", "class"], "class")
+            .domain("outlook", vec!["sunny", "rain"])
+            .domain("class", vec!["y", "n"])
+            .ignore(vec!["id"]);
+        let text = b"sunny,1,n\nfog,2,maybe\nrain,3\n?,4,y\n";
+
+        let (records, report) = schema.read_valid(&mut BufReader::new(text), DropInvalid).unwrap();
+
+        assert_eq!(2, records.len());
+        assert_eq!("?", records[1].get_attribute("outlook"));
+        assert_eq!(ValidationReport {
+            rows: 4,
+            invalid_rows: 2,
+            issues: vec![
+                Issue { row: 2, problem: UnexpectedValue("outlook".to_string(), "fog".to_string()) },
+                Issue { row: 2, problem: UnexpectedValue("class".to_string(), "maybe".to_string()) },
+                Issue { row: 3, problem: WrongArity(3, 2) }],
+        }, report);
+        assert_eq!("row 3: expected 3 fields, found 2", format!("{}", report.issues[2]).as_slice());
+
+        match schema.read_valid(&mut BufReader::new(text), RejectInvalid) {
+            Err(error) => assert_eq!(Some("2 of 4 rows invalid, first row 2: unexpected value fog for outlook".to_string()), error.detail),
+            Ok(_) => fail!("expected an error"),
+        }
+        assert!(schema.validate(records.as_slice()).is_valid());
+    }
+
+    #[test]
+    fn schema_keeps_valid_rows() {
+        let schema = Schema::new(vec!["outlook", "humidity", "class"], "class")
+            .domain("outlook", vec!["sunny", "rain"])
+            .numeric(vec!["humidity"]);
+        let row = |fields: Vec<&str>| fields.iter().map(|x| x.to_string()).collect::<Vec<String>>();
+        let rows = vec![
+            (3, row(vec!["fog", "70", "n"])),
+            (4, row(vec!["rain", "?", "y"])),
+            (6, row(vec!["rain", "high", "y"])),
+            (7, row(vec!["sunny", "65.5", "?"])),
+            (9, row(vec!["sunny", "80"]))];
+
+        let (kept, report) = schema.keep_valid(rows.clone(), DropInvalid).unwrap();
+
+        assert_eq!(1, kept.len());
+        assert_eq!("rain", kept[0].get_attribute("outlook"));
+        assert_eq!(vec![
+            Issue { row: 3, problem: UnexpectedValue("outlook".to_string(), "fog".to_string()) },
+            Issue { row: 6, problem: NotANumber("humidity".to_string(), "high".to_string()) },
+            Issue { row: 7, problem: MissingLabel },
+            Issue { row: 9, problem: WrongArity(3, 2) }], report.issues);
+        assert_eq!("row 6: high is not a number for humidity", format!("{}", report.issues[1]).as_slice());
+        assert!(schema.keep_valid(rows, RejectInvalid).is_err());
+    }
+}
+
+#[experimental]
+#[doc = "Reading the attribute descriptions in UCI repository `.names` files"]
+pub mod names {
+    use std::ascii::AsciiExt;
+    use std::io::{File, IoError, IoResult, InvalidInput};
+    use table::Schema;
+
+    #[doc = "A column described by a `.names` file."]
+    #[deriving(Clone, PartialEq, Show)]
+    pub struct Attribute {
+        /// Name as written in the file.
+        pub name: String,
+        /// Position of the column in the data file, counting from 0.
+        pub column: uint,
+        /// Values the file declares for the column, if it lists them.
+        pub domain: Option<Vec<String>>,
+    }
+
+    #[doc = "
+    The columns of a UCI dataset as described by its `.names` file.
+
+    These files are prose, so only the common conventions are understood. Columns are taken
+    from the numbered entries of the `Attribute Information` section, written `N. name: values`
+    where the values are a comma-separated list, optionally in parentheses after a count. The
+    class is the entry whose name starts with `class`, or the last column. Failing that, columns
+    are taken from a bracketed, comma-separated list of names in data file order, followed by a
+    class column named `class` whose values are the quoted strings on the `Classes` line. The
+    first quoted string in the numbered `Missing Attribute Values` section, if any, is taken as the
+    missing-value marker.
+    "]
+    #[deriving(Clone, PartialEq, Show)]
+    pub struct Names {
+        /// Every column, the class included, in data file order.
+        pub attributes: Vec<Attribute>,
+        /// Position of the class column.
+        pub label: uint,
+        /// Marker for missing values, if the file gives one.
+        pub missing_marker: Option<String>,
+    }
+
+    impl Names {
+        #[doc = "Parses the text of a `.names` file. Fails with `InvalidInput` if no columns are found."]
+        pub fn parse(text: &str) -> IoResult<Names> {
+            let lines: Vec<&str> = text.lines().collect();
+            let mut names = match attribute_information(lines.as_slice()) {
+                Some(names) => names,
+                None => match feature_list(text) {
+                    Some(names) => names,
+                    None => return Err(invalid("no attribute descriptions found", None)),
+                },
+            };
+            names.missing_marker = missing_marker(lines.as_slice());
+            Ok(names)
+        }
+
+        #[doc = "Parses the `.names` file at `path`."]
+        pub fn read_file(path: &Path) -> IoResult<Names> {
+            let text = try!(File::open(path).read_to_string());
+            Names::parse(text.as_slice())
+        }
+
+        pub fn label_attribute_name(&self) -> &str {
+            self.attributes[self.label].name.as_slice()
+        }
+
+        #[doc = "Values declared for the class, if the file lists them."]
+        pub fn class_values(&self) -> Option<&[String]> {
+            self.attributes[self.label].domain.as_ref().map(|x| x.as_slice())
+        }
+
+        #[doc = "
+        Comma-delimited schema with a column per attribute and the declared domains. The missing-value
+        marker is the file's, or `?` if it gives none.
+        "]
+        pub fn schema(&self) -> Schema {
+            let columns: Vec<&str> = self.attributes.iter().map(|x| x.name.as_slice()).collect();
+            let mut schema = Schema::new(columns, self.label_attribute_name());
+            for attribute in self.attributes.iter() {
+                match attribute.domain {
+                    Some(ref values) => schema = schema.domain(attribute.name.as_slice(), values.iter().map(|x| x.as_slice()).collect()),
+                    None => {},
+                }
+            }
+            match self.missing_marker {
+                Some(ref marker) => schema.missing_marker(marker.as_slice()),
+                None => schema,
+            }
+        }
+    }
+
+    fn invalid(desc: &'static str, detail: Option<String>) -> IoError {
+        IoError { kind: InvalidInput, desc: desc, detail: detail }
+    }
+
+    // Columns from the numbered entries of the "Attribute Information" section
+    fn attribute_information(lines: &[&str]) -> Option<Names> {
+        let mut attributes = Vec::new();
+        let mut in_section = false;
+        for line in lines.iter() {
+            let indented = line.starts_with(" ") || line.starts_with("\t");
+            match numbered(*line) {
+                // Sections are numbered at the start of the line, their entries indented
+                Some((_, title)) if !indented => {
+                    in_section = title.to_ascii_lower().as_slice().starts_with("attribute information");
+                },
+                Some((number, entry)) if in_section && number > 0 => match entry.find(':') {
+                    Some(colon) => attributes.push(Attribute {
+                        name: entry.slice_to(colon).trim().to_string(),
+                        column: number - 1,
+                        domain: domain(entry.slice_from(colon + 1)),
+                    }),
+                    None => {},
+                },
+                _ => {},
+            }
+        }
+        if attributes.is_empty() { return None; }
+
+        let label = attributes.iter()
+            .position(|x| x.name.as_slice().to_ascii_lower().as_slice().starts_with("class"))
+            .unwrap_or(attributes.len() - 1);
+        Some(Names { attributes: attributes, label: label, missing_marker: None })
+    }
+
+    // First quoted string in the numbered "Missing Attribute Values" section
+    fn missing_marker(lines: &[&str]) -> Option<String> {
+        let mut in_section = false;
+        for line in lines.iter() {
+            let indented = line.starts_with(" ") || line.starts_with("\t");
+            match numbered(*line) {
+                Some((_, title)) if !indented => {
+                    in_section = title.to_ascii_lower().as_slice().starts_with("missing attribute");
+                },
+                _ => {},
+            }
+            if in_section {
+                match quoted(*line).into_iter().next() {
+                    Some(marker) => return Some(marker),
+                    None => {},
+                }
+            }
+        }
+        None
+    }
+
+    // Columns from a bracketed list of names, followed by the class
+    fn feature_list(text: &str) -> Option<Names> {
+        let start = match text.find('[') { Some(start) => start, None => return None };
+        let end = match text.slice_from(start).find(']') { Some(end) => start + end, None => return None };
+        let mut attributes: Vec<Attribute> = text.slice(start + 1, end).split(',')
+            .map(|x| x.trim())
+            .filter(|x| !x.is_empty())
+            .enumerate()
+            .map(|(column, name)| Attribute { name: name.to_string(), column: column, domain: None })
+            .collect();
+        if attributes.is_empty() { return None; }
+
+        let classes: Vec<String> = text.lines()
+            .filter(|x| x.trim_left().to_ascii_lower().as_slice().starts_with("classes"))
+            .flat_map(|x| quoted(x).into_iter())
+            .collect();
+        let label = attributes.len();
+        attributes.push(Attribute {
+            name: "class".to_string(),
+            column: label,
+            domain: if classes.is_empty() { None } else { Some(classes) },
+        });
+        Some(Names { attributes: attributes, label: label, missing_marker: None })
+    }
+
+    // The number and text of a line like "  7. Attribute Information:"
+    fn numbered(line: &str) -> Option<(uint, &str)> {
+        let trimmed = line.trim_left();
+        let digits = trimmed.chars().take_while(|x| x.is_digit()).count();
+        let rest = trimmed.slice_from(digits);
+        if digits == 0 || !rest.starts_with(". ") && !rest.starts_with(".\t") {
+            return None;
+        }
+        from_str::<uint>(trimmed.slice_to(digits)).map(|number| (number, rest.slice_from(1).trim()))
+    }
+
+    // Values listed after an attribute's name, e.g. "2 (y,n)" or "1, 2, 3". Free text such as
+    // "(A unique symbol for each instance)" declares no values.
+    fn domain(spec: &str) -> Option<Vec<String>> {
+        let spec = spec.trim();
+        let list = match (spec.find('('), spec.rfind(')')) {
+            (Some(open), Some(close)) if open < close => spec.slice(open + 1, close),
+            _ => spec,
+        };
+        let values: Vec<&str> = list.split(',').map(|x| x.trim()).collect();
+        if values.iter().any(|x| x.is_empty() || x.contains_char(' ')) {
+            None
+        } else {
+            Some(values.iter().map(|x| x.to_string()).collect())
+        }
+    }
+
+    // Double-quoted strings in a line
+    fn quoted(line: &str) -> Vec<String> {
+        line.split('"').enumerate().filter(|&(i, _)| i % 2 == 1).map(|(_, x)| x.to_string()).collect()
+    }
+
+    #[test]
+    fn names_numbered_attributes() {
+        let names = Names::parse(include_str!("../data/voting-records/house-votes-84.names")).unwrap();
+
+        assert_eq!(17, names.attributes.len());
+        assert_eq!("Class Name", names.label_attribute_name());
+        assert_eq!(Some(["democrat".to_string(), "republican".to_string()].as_slice()), names.class_values());
+        assert_eq!(Attribute { name: "mx-missile".to_string(), column: 9, domain: Some(vec!["y".to_string(), "n".to_string()]) },
+                   names.attributes[9]);
+        assert_eq!(Some("?".to_string()), names.missing_marker);
+
+        let names = Names::parse(include_str!("../data/monks-problems/monks.names")).unwrap();
+        assert_eq!(vec!["class", "a1", "a2", "a3", "a4", "a5", "a6", "Id"],
+                   names.attributes.iter().map(|x| x.name.as_slice()).collect());
+        assert_eq!(0, names.label);
+        assert_eq!(Some(vec!["1".to_string(), "2".to_string(), "3".to_string(), "4".to_string()]), names.attributes[5].domain);
+        assert_eq!(None, names.attributes[7].domain);
+        assert_eq!(None, names.missing_marker);
+    }
+
+    #[test]
+    fn names_feature_list() {
+        let names = Names::parse(include_str!("../data/king-rook-vs-king-pawn/kr-vs-kp.names")).unwrap();
+
+        assert_eq!(37, names.attributes.len());
+        assert_eq!("bkblk", names.attributes[0].name.as_slice());
+        assert_eq!("wtoeg", names.attributes[35].name.as_slice());
+        assert_eq!(36, names.label);
+        assert_eq!(Some(["won".to_string(), "nowin".to_string()].as_slice()), names.class_values());
+        // Quoted class values elsewhere in the prose are not a marker
+        assert_eq!(None, names.missing_marker);
+    }
+
+    #[test]
+    fn names_schema() {
+        let names = Names::parse("7. Attribute Information:\n   1. outlook: sunny, rain\n   2. class: y, n\n").unwrap();
+        let schema = names.schema();
+
+        assert_eq!(vec!["outlook"], schema.attribute_names());
+        assert_eq!("class", schema.label_attribute_name());
+        assert_eq!(Some(["sunny".to_string(), "rain".to_string()].as_slice()), schema.declared_domain("outlook"));
+        assert!(Names::parse("1. Title: nothing to see").is_err());
+    }
+}
+
+#[experimental]
+#[doc = "Reading datasets in Quinlan's C4.5 format"]
+pub mod c45 {
+    use std::ascii::AsciiExt;
+    use std::io::{BufferedReader, BufReader, File, IoError, IoResult, InvalidInput};
+    use std::io::fs::PathExtensions;
+    use tree::Record;
+    use table::{Schema, TableRecord, InvalidRows, ValidationReport};
+    use id3::TreeBuilder;
+    #[cfg(test)]
+    use table::{DropInvalid, RejectInvalid};
+
+    #[doc = "
+    A C4.5 dataset: `stem.names` describing the attributes, the training cases in `stem.data`
+    and, optionally, test cases in `stem.test`.
+
+    The `.names` file lists the class values first, then one `name: values` entry per column of
+    the data files, each entry ending with a period. The values are a comma-separated list, or
+    `continuous` for numeric attributes, `discrete N` for attributes whose values are only seen
+    in the data, or `ignore` for columns to skip. Everything after `|` on a line is a comment.
+    The class follows the attributes in each case and is named `class`; `?` marks missing values.
+    "]
+    #[deriving(Clone)]
+    pub struct Stem {
+        stem: Path,
+        schema: Schema,
+        numeric: Vec<String>,
+    }
+
+    impl Stem {
+        #[doc = "Reads `stem.names`."]
+        pub fn open(stem: &Path) -> IoResult<Stem> {
+            let text = try!(File::open(&with_suffix(stem, ".names")).read_to_string());
+            Stem::parse_names(stem, text.as_slice())
+        }
+
+        #[doc = "Dataset at `stem` described by the given `.names` text."]
+        pub fn parse_names(stem: &Path, text: &str) -> IoResult<Stem> {
+            // Comments run to the end of the line
+            let text: Vec<&str> = text.lines().map(|x| match x.find('|') {
+                Some(bar) => x.slice_to(bar),
+                None => x,
+            }).collect();
+            let entries = entries(text.connect("\n").as_slice());
+            if entries.is_empty() {
+                return Err(invalid("no class values", None));
+            }
+
+            let classes: Vec<&str> = entries[0].as_slice().split(',').map(|x| x.trim()).collect();
+            let mut columns = Vec::new();
+            let mut domains = Vec::new();
+            let mut ignored = Vec::new();
+            let mut numeric = Vec::new();
+            for entry in entries.slice_from(1).iter() {
+                let entry = entry.as_slice();
+                let colon = match entry.find(':') {
+                    Some(colon) => colon,
+                    None => return Err(invalid("attribute without values", Some(entry.to_string()))),
+                };
+                let name = entry.slice_to(colon).trim();
+                let spec = entry.slice_from(colon + 1).trim();
+                let keyword = spec.to_ascii_lower();
+                if keyword.as_slice() == "continuous" {
+                    numeric.push(name.to_string());
+                } else if keyword.as_slice() == "ignore" {
+                    ignored.push(name);
+                } else if !keyword.as_slice().starts_with("discrete") {
+                    domains.push((name, spec.split(',').map(|x| x.trim()).collect::<Vec<&str>>()));
+                }
+                columns.push(name);
+            }
+            if columns.iter().any(|x| *x == "class") {
+                return Err(invalid("attribute named class", None));
+            }
+
+            columns.push("class");
+            let mut schema = Schema::new(columns, "class")
+                .domain("class", classes)
+                .ignore(ignored)
+                .numeric(numeric.iter().map(|x| x.as_slice()).collect());
+            for (name, values) in domains.into_iter() {
+                schema = schema.domain(name, values);
+            }
+            Ok(Stem { stem: stem.clone(), schema: schema, numeric: numeric })
+        }
+
+        #[doc = "Layout of the data files, the class last."]
+        pub fn schema(&self) -> &Schema {
+            &self.schema
+        }
+
+        #[doc = "Attributes declared `continuous`."]
+        pub fn numeric_attributes(&self) -> Vec<&str> {
+            self.numeric.iter().map(|x| x.as_slice()).collect()
+        }
+
+        #[doc = "Builder for trees predicting the class from every attribute not ignored."]
+        pub fn tree_builder(&self) -> TreeBuilder {
+            TreeBuilder::new(self.schema.label_attribute_name(), self.schema.attribute_names())
+                .numeric_attributes(self.numeric_attributes())
+                .missing_values(self.schema.missing_values())
+        }
+
+        #[doc = "The training cases in `stem.data`; see `read`."]
+        pub fn data(&self, invalid: InvalidRows) -> IoResult<(Vec<TableRecord>, ValidationReport)> {
+            let mut reader = BufferedReader::new(try!(File::open(&with_suffix(&self.stem, ".data"))));
+            self.read(&mut reader, invalid)
+        }
+
+        #[doc = "The test cases in `stem.test`, if there is such a file; see `read`."]
+        pub fn test(&self, invalid: InvalidRows) -> IoResult<Option<(Vec<TableRecord>, ValidationReport)>> {
+            let path = with_suffix(&self.stem, ".test");
+            if !path.exists() {
+                return Ok(None);
+            }
+            let mut reader = BufferedReader::new(try!(File::open(&path)));
+            self.read(&mut reader, invalid).map(|x| Some(x))
+        }
+
+        #[doc = "
+        Reads cases in the layout of the data files, checking them against the declared values
+        as `Schema::read_valid` does. A period ending a case, as some `.test` files have, is
+        dropped when what precedes it is a class value.
+        "]
+        pub fn read<B: Buffer>(&self, reader: &mut B, invalid: InvalidRows) -> IoResult<(Vec<TableRecord>, ValidationReport)> {
+            let classes = self.schema.declared_domain("class").unwrap();
+            let mut text = String::new();
+            for line in reader.lines() {
+                let line = try!(line);
+                let line = line.as_slice().trim_right();
+                // The class is the last field
+                let class = line.slice_from(line.rfind(',').map_or(0, |x| x + 1)).trim();
+                let period = class.ends_with(".") && classes.iter().any(|x| x.as_slice() == class.slice_to(class.len() - 1));
+                text.push_str(if period { line.slice_to(line.len() - 1) } else { line });
+                text.push('\n');
+            }
+            self.schema.read_valid(&mut BufReader::new(text.as_bytes()), invalid)
+        }
+    }
+
+    fn invalid(desc: &'static str, detail: Option<String>) -> IoError {
+        IoError { kind: InvalidInput, desc: desc, detail: detail }
+    }
+
+    fn with_suffix(stem: &Path, suffix: &str) -> Path {
+        let mut bytes = stem.as_vec().to_vec();
+        bytes.push_all(suffix.as_bytes());
+        Path::new(bytes)
+    }
+
+    // Entries of a .names file, each ended by a period followed by whitespace or the end of
+    // the text, with line breaks inside entries allowed
+    fn entries(text: &str) -> Vec<String> {
+        let mut entries = Vec::new();
+        let mut entry = String::new();
+        let mut chars = text.chars().peekable();
+        loop {
+            match chars.next() {
+                Some('.') if chars.peek().map_or(true, |x| x.is_whitespace()) => {
+                    entries.push(entry.as_slice().trim().to_string());
+                    entry = String::new();
+                },
+                Some(c) => entry.push(c),
+                None => break,
+            }
+        }
+        if !entry.as_slice().trim().is_empty() {
+            entries.push(entry.as_slice().trim().to_string());
+        }
+        entries.into_iter().filter(|x| !x.is_empty()).collect()
+    }
+
+    #[cfg(test)]
+    fn golf() -> Stem {
+        Stem::parse_names(&Path::new("golf"), "
+            | Quinlan's golf example
+            Play, Don't Play.
+
+            outlook: sunny, overcast,
+                     rain.
+            temperature: continuous.
+            humidity: continuous.   | percent
+            windy: true, false.
+            day: ignore.
+        ").unwrap()
+    }
+
+    #[test]
+    fn stem_names() {
+        let stem = golf();
+
+        assert_eq!(vec!["outlook", "temperature", "humidity", "windy"], stem.schema().attribute_names());
+        assert_eq!(vec!["temperature", "humidity"], stem.numeric_attributes());
+        assert_eq!("class", stem.schema().label_attribute_name());
+        assert_eq!(Some(["Play".to_string(), "Don't Play".to_string()].as_slice()), stem.schema().declared_domain("class"));
+        assert_eq!(Some(["sunny".to_string(), "overcast".to_string(), "rain".to_string()].as_slice()),
+                   stem.schema().declared_domain("outlook"));
+        assert_eq!(None, stem.schema().declared_domain("temperature"));
+        assert!(Stem::parse_names(&Path::new("bad"), "y, n.\nclass: a, b.\n").is_err());
+    }
+
+    #[test]
+    fn stem_reads_cases() {
+        let stem = golf();
+        let text = b"sunny, 85, 85, false, 1, Don't Play\nrain, 71, ?, true, 14, Play.\nfog, 60, 60, false, 2, Play\n";
+        let (cases, report) = stem.read(&mut BufReader::new(text), DropInvalid).unwrap();
+
+        assert_eq!(2, cases.len());
+        assert_eq!(1, report.invalid_rows);
+        assert_eq!(3, report.issues[0].row);
+        assert_eq!("Don't Play", cases[0].get_label());
+        assert_eq!("Play", cases[1].get_label());
+        assert_eq!("?", cases[1].get_attribute("humidity"));
+
+        let tree = stem.tree_builder().build(&cases.iter().collect());
+        assert_eq!(2, tree.leaf_count());
+        assert!(stem.read(&mut BufReader::new(text), RejectInvalid).is_err());
+    }
+}
+
+#[experimental]
+#[doc = "Reading and writing Weka's ARFF format"]
+pub mod arff {
+    use std::ascii::AsciiExt;
+    use std::collections::TreeSet;
+    use std::io::{File, IoError, IoResult, InvalidInput};
+    use tree::Record;
+    use table::{Schema, TableRecord, InvalidRows, ValidationReport};
+    use dataset::Dataset;
+    use id3::TreeBuilder;
+    #[cfg(test)]
+    use std::io::MemWriter;
+    #[cfg(test)]
+    use tree::{record, Leaf};
+    #[cfg(test)]
+    use table::{DropInvalid, RejectInvalid, Issue, UnexpectedValue, NotANumber, MissingLabel, WrongArity};
+
+    #[doc = "The type declared for an ARFF attribute."]
+    #[deriving(Clone, PartialEq, Show)]
+    pub enum AttributeType {
+        /// One of the listed values.
+        Nominal(Vec<String>),
+        /// A number, declared `numeric`, `real` or `integer`.
+        Numeric,
+        /// Free text, declared `string` or `date`.
+        Text,
+    }
+
+    #[doc = "An attribute declared in an ARFF header."]
+    #[deriving(Clone, PartialEq, Show)]
+    pub struct ArffAttribute {
+        pub name: String,
+        pub kind: AttributeType,
+    }
+
+    #[doc = "
+    The contents of an ARFF file. As in Weka, the last attribute is the label.
+
+    Rows may be dense or sparse. Values left out of a sparse row are `0` for numeric attributes,
+    the first value of nominal ones and empty for the rest. `?` marks missing values. Rows are
+    kept as read; see `valid_records` for checking them against the header.
+    "]
+    #[deriving(Clone, Show)]
+    pub struct Arff {
+        relation: String,
+        attributes: Vec<ArffAttribute>,
+        schema: Schema,
+        // Line number and values of each row
+        rows: Vec<(uint, Vec<String>)>,
+    }
+
+    impl Arff {
+        #[doc = "Parses the text of an ARFF file. Fails with `InvalidInput` on the first malformed line."]
+        pub fn parse(text: &str) -> IoResult<Arff> {
+            let mut relation = String::new();
+            let mut attributes = Vec::new();
+            let mut rows = Vec::new();
+            let mut in_data = false;
+            for (number, line) in text.lines().enumerate() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with("%") { continue; }
+                let keyword = line.to_ascii_lower();
+                let keyword = keyword.as_slice();
+
+                if in_data {
+                    let row = if line.starts_with("{") {
+                        try!(sparse_row(line, attributes.as_slice(), number + 1))
+                    } else {
+                        split(line, ',').iter().map(|x| unquote(x.as_slice())).collect()
+                    };
+                    rows.push((number + 1, row));
+                } else if keyword.starts_with("@relation") {
+                    relation = unquote(line.slice_from(9).trim());
+                } else if keyword.starts_with("@attribute") {
+                    attributes.push(try!(attribute(line.slice_from(10).trim(), number + 1)));
+                } else if keyword.starts_with("@data") {
+                    in_data = true;
+                } else {
+                    return Err(invalid("unexpected line", format!("line {}: {}", number + 1, line)));
+                }
+            }
+            if attributes.is_empty() {
+                return Err(invalid("no attributes", String::new()));
+            }
+
+            let mut schema = Schema::new(attributes.iter().map(|x| x.name.as_slice()).collect(),
+                                         attributes.last().unwrap().name.as_slice());
+            for attribute in attributes.iter() {
+                match attribute.kind {
+                    Nominal(ref values) => schema = schema.domain(attribute.name.as_slice(), values.iter().map(|x| x.as_slice()).collect()),
+                    _ => {},
+                }
+            }
+            schema = schema.numeric(attributes.iter().filter(|x| x.kind == Numeric).map(|x| x.name.as_slice()).collect());
+            Ok(Arff { relation: relation, attributes: attributes, schema: schema, rows: rows })
+        }
+
+        #[doc = "Parses the ARFF file at `path`."]
+        pub fn read_file(path: &Path) -> IoResult<Arff> {
+            let text = try!(File::open(path).read_to_string());
+            Arff::parse(text.as_slice())
+        }
+
+        pub fn relation(&self) -> &str {
+            self.relation.as_slice()
+        }
+
+        pub fn attributes(&self) -> &[ArffAttribute] {
+            self.attributes.as_slice()
+        }
+
+        #[doc = "Layout of the rows, with the declared values of nominal attributes."]
+        pub fn schema(&self) -> &Schema {
+            &self.schema
+        }
+
+        #[doc = "The rows with a value for every attribute, read as records."]
+        pub fn records(&self) -> Vec<TableRecord> {
+            self.rows.iter()
+                .filter(|&&(_, ref values)| values.len() == self.attributes.len())
+                .map(|&(_, ref values)| TableRecord::new(&self.schema, values.clone()))
+                .collect()
+        }
+
+        #[doc = "
+        The rows with a value for every attribute, nominal values among those declared, numbers
+        for numeric attributes and a label, reported by line as `Schema::read_valid` does.
+        "]
+        pub fn valid_records(&self, invalid: InvalidRows) -> IoResult<(Vec<TableRecord>, ValidationReport)> {
+            self.schema.keep_valid(self.rows.clone(), invalid)
+        }
+
+        #[doc = "Attributes declared numeric."]
+        pub fn numeric_attributes(&self) -> Vec<&str> {
+            self.attributes.iter().filter(|x| x.kind == Numeric).map(|x| x.name.as_slice()).collect()
+        }
+
+        #[doc = "The rows encoded for training, leaving out `string` and `date` attributes."]
+        pub fn dataset(&self) -> Dataset {
+            let records = self.records();
+            Dataset::from_records(&records.iter().collect(), self.schema.label_attribute_name(), self.training_attributes())
+        }
+
+        #[doc = "Builder for trees predicting the last attribute from the others, leaving out `string` and `date` attributes."]
+        pub fn tree_builder(&self) -> TreeBuilder {
+            TreeBuilder::new(self.schema.label_attribute_name(), self.training_attributes())
+                .numeric_attributes(self.numeric_attributes())
+                .missing_values(self.schema.missing_values())
+        }
+
+        // Attributes other than the label that hold a number or a category
+        fn training_attributes(&self) -> Vec<&str> {
+            self.attributes.iter()
+                .take(self.attributes.len() - 1)
+                .filter(|x| x.kind != Text)
+                .map(|x| x.name.as_slice())
+                .collect()
+        }
+    }
+
+    #[doc = "
+    Writes records as a dense ARFF file with a column for each of the schema's attributes, in
+    file order, followed by its label, since readers take the last column as the label. Columns
+    listed in `numeric_attributes` are declared numeric and the rest nominal, with their declared
+    values followed by any others found in the records. Values equal to the schema's
+    missing-value marker are written as `?`.
+    "]
+    pub fn write<W: Writer, T: Record>(writer: &mut W, relation: &str, schema: &Schema, numeric_attributes: Vec<&str>,
+                                       records: &[T]) -> IoResult<()> {
+        let mut columns = schema.attribute_names();
+        columns.push(schema.label_attribute_name());
+
+        try!(writeln!(writer, "@relation {}", quote(relation)));
+        try!(writeln!(writer, ""));
+        for column in columns.iter() {
+            if numeric_attributes.contains(column) {
+                try!(writeln!(writer, "@attribute {} numeric", quote(*column)));
+                continue;
+            }
+            let mut values: Vec<String> = match schema.declared_domain(*column) {
+                Some(declared) => declared.to_vec(),
+                None => vec![],
+            };
+            let found: TreeSet<&str> = records.iter()
+                .map(|x| x.get_attribute(*column))
+                .filter(|x| *x != schema.marker() && !values.iter().any(|value| value.as_slice() == *x))
+                .collect();
+            values.extend(found.into_iter().map(|x| x.to_string()));
+            let values: Vec<String> = values.iter().map(|x| quote(x.as_slice())).collect();
+            try!(writeln!(writer, "@attribute {} {{{}}}", quote(*column), values.connect(",")));
+        }
+
+        try!(writeln!(writer, ""));
+        try!(writeln!(writer, "@data"));
+        for record in records.iter() {
+            let values: Vec<String> = columns.iter().map(|column| {
+                let value = record.get_attribute(*column);
+                if value == schema.marker() { "?".to_string() } else { quote(value) }
+            }).collect();
+            try!(writeln!(writer, "{}", values.connect(",")));
+        }
+        Ok(())
+    }
+
+    fn invalid(desc: &'static str, detail: String) -> IoError {
+        IoError { kind: InvalidInput, desc: desc, detail: if detail.is_empty() { None } else { Some(detail) } }
+    }
+
+    // The name and type following "@attribute"
+    fn attribute(declaration: &str, line: uint) -> IoResult<ArffAttribute> {
+        // Quoted names run to the closing quote, others to the first whitespace
+        let end = match declaration.chars().next() {
+            Some(quote) if quote == '\'' || quote == '"' => {
+                let mut escaped = false;
+                let close = declaration.char_indices().skip(1).find(|&(_, c)| {
+                    let found = !escaped && c == quote;
+                    escaped = !escaped && c == '\\';
+                    found
+                });
+                match close {
+                    Some((index, _)) => index + 1,
+                    None => return Err(invalid("unterminated quoted name", format!("line {}: {}", line, declaration))),
+                }
+            },
+            _ => declaration.find(|c: char| c.is_whitespace()).unwrap_or(declaration.len()),
+        };
+        let name = unquote(declaration.slice_to(end));
+        let kind = declaration.slice_from(end).trim();
+        let keyword = kind.to_ascii_lower();
+
+        let kind = if kind.starts_with("{") && kind.ends_with("}") {
+            Nominal(split(kind.slice(1, kind.len() - 1), ',').iter().map(|x| unquote(x.as_slice())).collect())
+        } else if ["numeric", "real", "integer"].iter().any(|x| keyword.as_slice() == *x) {
+            Numeric
+        } else if keyword.as_slice() == "string" || keyword.as_slice().starts_with("date") {
+            Text
+        } else {
+            return Err(invalid("unsupported attribute type", format!("line {}: {}", line, kind)));
+        };
+        Ok(ArffAttribute { name: name, kind: kind })
+    }
+
+    // A row written "{index value, ...}", with the values left out filled in
+    fn sparse_row(line: &str, attributes: &[ArffAttribute], number: uint) -> IoResult<Vec<String>> {
+        let mut row: Vec<String> = attributes.iter().map(|x| match x.kind {
+            Numeric => "0".to_string(),
+            Nominal(ref values) if !values.is_empty() => values[0].clone(),
+            _ => String::new(),
+        }).collect();
+
+        if line.len() < 2 || !line.ends_with("}") {
+            return Err(invalid("unterminated sparse row", format!("line {}: {}", number, line)));
+        }
+        let inner = line.slice(1, line.len() - 1).trim();
+        if inner.is_empty() { return Ok(row); }
+        for entry in split(inner, ',').iter() {
+            let entry = entry.as_slice().trim();
+            let space = entry.find(|c: char| c.is_whitespace()).unwrap_or(entry.len());
+            match from_str::<uint>(entry.slice_to(space)) {
+                Some(index) if index < row.len() => *row.get_mut(index) = unquote(entry.slice_from(space).trim()),
+                _ => return Err(invalid("bad sparse value", format!("line {}: {}", number, entry))),
+            }
+        }
+        Ok(row)
+    }
+
+    // Pieces of text between separators outside quotes, quotes kept
+    fn split(text: &str, separator: char) -> Vec<String> {
+        let mut pieces = vec![String::new()];
+        let mut quote = None;
+        let mut escaped = false;
+        for c in text.chars() {
+            match quote {
+                _ if escaped => escaped = false,
+                Some(_) if c == '\\' => escaped = true,
+                Some(open) if c == open => quote = None,
+                None if c == '\'' || c == '"' => quote = Some(c),
+                None if c == separator => {
+                    pieces.push(String::new());
+                    continue;
+                },
+                _ => {},
+            }
+            pieces.last_mut().unwrap().push(c);
+        }
+        pieces.into_iter().map(|x| x.as_slice().trim().to_string()).collect()
+    }
+
+    fn unquote(text: &str) -> String {
+        let text = text.trim();
+        if text.len() < 2 || !(text.starts_with("'") && text.ends_with("'") || text.starts_with("\"") && text.ends_with("\"")) {
+            return text.to_string();
+        }
+        let mut unquoted = String::new();
+        let mut escaped = false;
+        for c in text.slice(1, text.len() - 1).chars() {
+            if !escaped && c == '\\' {
+                escaped = true;
+                continue;
+            }
+            escaped = false;
+            unquoted.push(c);
+        }
+        unquoted
+    }
+
+    fn quote(text: &str) -> String {
+        if !text.is_empty() && text != "?" && !text.chars().any(|c| c.is_whitespace() || ",{}%'\"\\".contains_char(c)) {
+            return text.to_string();
+        }
+        let mut quoted = String::from_str("'");
+        for c in text.chars() {
+            if c == '\'' || c == '\\' { quoted.push('\\'); }
+            quoted.push(c);
+        }
+        quoted.push('\'');
+        quoted
+    }
+
+    #[test]
+    fn arff_reads_dense_rows() {
+        let arff = Arff::parse("
+            % Quinlan's golf example
+            @RELATION golf
+
+            @ATTRIBUTE outlook {sunny, overcast, rain}
+            @ATTRIBUTE temperature REAL
+            @ATTRIBUTE 'wind speed' string
+            @ATTRIBUTE play {yes, 'no way'}
+
+            @DATA
+            sunny, 85, 'light, gusty', 'no way'
+            rain, ?, calm, yes
+        ").unwrap();
+
+        assert_eq!("golf", arff.relation());
+        assert_eq!(ArffAttribute { name: "wind speed".to_string(), kind: Text }, arff.attributes()[2]);
+        assert_eq!(vec!["temperature"], arff.numeric_attributes());
+        assert_eq!("play", arff.schema().label_attribute_name());
+        assert_eq!(Some(["yes".to_string(), "no way".to_string()].as_slice()), arff.schema().declared_domain("play"));
+
+        let records = arff.records();
+        assert_eq!("light, gusty", records[0].get_attribute("wind speed"));
+        assert_eq!("no way", records[0].get_label());
+        assert_eq!("?", records[1].get_attribute("temperature"));
+        assert_eq!(2, arff.dataset().len());
+        // Rows with the wrong number of values are kept for `valid_records` to report
+        assert!(Arff::parse("@attribute a {x}\n@data\nx, y\n").unwrap().records().is_empty());
+    }
+
+    #[test]
+    fn arff_reads_sparse_rows() {
+        let arff = Arff::parse("@attribute x numeric\n@attribute s string\n@attribute c {p, q}\n@data\n{1 'a b', 2 q}\n{}\n").unwrap();
+
+        let records = arff.records();
+        assert_eq!(vec!["0".to_string(), "a b".to_string(), "q".to_string()], records[0].fields().to_vec());
+        assert_eq!(vec!["0".to_string(), String::new(), "p".to_string()], records[1].fields().to_vec());
+        assert!(Arff::parse("@attribute x numeric\n@data\n{3 1}\n").is_err());
+        assert!(Arff::parse("@attribute x numeric\n@data\n{\n").is_err());
+        assert!(Arff::parse("@attribute x numeric\n@data\n{0 1\n").is_err());
+    }
+
+    #[test]
+    fn arff_checks_rows() {
+        let arff = Arff::parse("@attribute a {x, y}\n@attribute n numeric\n@attribute c {p, q}\n@data\n\
+                                x, 1, p\nz, 2, q\n\n?, 3, q\ny, abc, p\nx, 4, ?\nx, 5\n").unwrap();
+
+        let (records, report) = arff.valid_records(DropInvalid).unwrap();
+
+        assert_eq!(5, arff.records().len());
+        assert_eq!(2, records.len());
+        assert_eq!(vec![
+            Issue { row: 6, problem: UnexpectedValue("a".to_string(), "z".to_string()) },
+            Issue { row: 9, problem: NotANumber("n".to_string(), "abc".to_string()) },
+            Issue { row: 10, problem: MissingLabel },
+            Issue { row: 11, problem: WrongArity(3, 2) }], report.issues);
+        assert!(arff.valid_records(RejectInvalid).is_err());
+    }
+
+    #[test]
+    fn arff_tree_builder_skips_text() {
+        let arff = Arff::parse("@attribute s string\n@attribute c {p, q}\n@data\na, p\nb, q\n").unwrap();
+        let records = arff.records();
+
+        match arff.tree_builder().build(&records.iter().collect()) {
+            Leaf(ref label, _) => assert_eq!("p".to_string(), *label),
+            _ => fail!("expected a leaf"),
+        }
+        assert_eq!(None, arff.dataset().column_index("s"));
+    }
+
+    #[test]
+    fn arff_reads_quoted_names() {
+        let arff = Arff::parse("@attribute 'wind speed'\tnumeric\n@attribute 'it\\'s' {p, q}\n@data\n1, p\n").unwrap();
+
+        assert_eq!(ArffAttribute { name: "wind speed".to_string(), kind: Numeric }, arff.attributes()[0]);
+        assert_eq!("it's", arff.attributes()[1].name.as_slice());
+        assert!(Arff::parse("@attribute 'wind speed numeric\n").is_err());
+    }
+
+    #[test]
+    fn arff_round_trip() {
+        let r1 = record(vec![("outlook", "sunny"), ("humidity", "70"), ("class", "don't play")]);
+        let r2 = record(vec![("outlook", "?"), ("humidity", "90.5"), ("class", "play")]);
+        let schema = Schema::new(vec!["outlook", "humidity", "class"], "class").domain("outlook", vec!["sunny", "rain"]);
+        let mut writer = MemWriter::new();
+
+        write(&mut writer, "weather", &schema, vec!["humidity"], [r1, r2].as_slice()).unwrap();
+
+        let text = String::from_utf8(writer.unwrap()).unwrap();
+        assert_eq!("@relation weather\n\n\
+                    @attribute outlook {sunny,rain}\n\
+                    @attribute humidity numeric\n\
+                    @attribute class {'don\\'t play',play}\n\n\
+                    @data\n\
+                    sunny,70,'don\\'t play'\n\
+                    ?,90.5,play\n", text.as_slice());
+        let arff = Arff::parse(text.as_slice()).unwrap();
+        assert_eq!("don't play", arff.records()[0].get_label());
+        assert_eq!("?", arff.records()[1].get_attribute("outlook"));
+    }
+
+    #[test]
+    fn arff_round_trip_label_first() {
+        let r1 = record(vec![("class", "play"), ("outlook", "sunny"), ("humidity", "70")]);
+        let r2 = record(vec![("class", "stay"), ("outlook", "rain"), ("humidity", "90")]);
+        let schema = Schema::new(vec!["class", "outlook", "humidity"], "class");
+        let mut writer = MemWriter::new();
+
+        write(&mut writer, "weather", &schema, vec!["humidity"], [r1, r2].as_slice()).unwrap();
+
+        let text = String::from_utf8(writer.unwrap()).unwrap();
+        assert_eq!("@relation weather\n\n\
+                    @attribute outlook {rain,sunny}\n\
+                    @attribute humidity numeric\n\
+                    @attribute class {play,stay}\n\n\
+                    @data\n\
+                    sunny,70,play\n\
+                    rain,90,stay\n", text.as_slice());
+        let arff = Arff::parse(text.as_slice()).unwrap();
+        assert_eq!("class", arff.schema().label_attribute_name());
+        assert_eq!("stay", arff.records()[1].get_label());
+        assert_eq!("rain", arff.records()[1].get_attribute("outlook"));
     }
 }
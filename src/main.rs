@@ -1,305 +1,116 @@
-extern crate csv;
-extern crate serialize;
 extern crate decision_tree;
 
+use std::os;
+use std::io::File;
 use std::path::Path;
 use std::rand::{task_rng, Rng};
 use decision_tree::{id3, tree};
+use decision_tree::names::Names;
+use decision_tree::c45::Stem;
+use decision_tree::arff;
+use decision_tree::arff::Arff;
+use decision_tree::table::{Schema, TableRecord, ValidationReport, InvalidRows, DropInvalid, RejectInvalid};
 
-#[deriving(Clone)]
-struct VotingRecord {
-    fields: Vec<String>,
-}
-
-#[deriving(Clone)]
-struct MonkRecord {
-    fields: Vec<String>,
-}
-
-#[deriving(Clone)]
-struct ChessRecord {
-    fields: Vec<String>,
-}
-
-static SPACE: u8 = ' ' as u8;
-static class_name: &'static str = "class_name";
-// Voting Records attribute names
-static handicapped: &'static str = "handicapped";
-static water_project: &'static str = "water_project";
-static budget_resolution: &'static str = "budget_resolution";
-static physician_freeze: &'static str = "physician_freeze";
-static el_salvador_aid: &'static str = "el_salvador_aid";
-static religious_schools: &'static str = "religious_schools";
-static anti_sattelite_ban: &'static str = "anti_sattelite_ban";
-static aid_to_contras: &'static str = "aid_to_contras";
-static mx_missile: &'static str = "mx_missile";
-static immigration: &'static str = "immigration";
-static synfuels_cutback: &'static str = "synfuels_cutback";
-static education_spending: &'static str = "education_spending";
-static right_to_sue: &'static str = "right_to_sue";
-static crime: &'static str = "crime";
-static duty_free_exports: &'static str = "duty_free_exports";
-static export_south_africa: &'static str = "export_south_africa";
-// Monks Problems attributes names
-static a1: &'static str = "a1";
-static a2: &'static str = "a2";
-static a3: &'static str = "a3";
-static a4: &'static str = "a4";
-static a5: &'static str = "a5";
-static a6: &'static str = "a6";
-// chess attribute names
-static bkblk: &'static str = "bkblk";
-static bknwy: &'static str = "bknwy";
-static bkon8: &'static str = "bkon8";
-static bkona: &'static str = "bkona";
-static bkspr: &'static str = "bkspr";
-static bkxbq: &'static str = "bkxbq";
-static bkxcr: &'static str = "bkxcr";
-static bkxwp: &'static str = "bkxwp";
-static blxwp: &'static str = "blxwp";
-static bxqsq: &'static str = "bxqsq";
-static cntxt: &'static str = "cntxt";
-static dsopp: &'static str = "dsopp";
-static dwipd: &'static str = "dwipd";
-static hdchk: &'static str = "hdchk";
-static katri: &'static str = "katri";
-static mulch: &'static str = "mulch";
-static qxmsq: &'static str = "qxmsq";
-static r2ar8: &'static str = "r2ar8";
-static reskd: &'static str = "reskd";
-static reskr: &'static str = "reskr";
-static rimmx: &'static str = "rimmx";
-static rkxwp: &'static str = "rkxwp";
-static rxmsq: &'static str = "rxmsq";
-static simpl: &'static str = "simpl";
-static skach: &'static str = "skach";
-static skewr: &'static str = "skewr";
-static skrxp: &'static str = "skrxp";
-static spcop: &'static str = "spcop";
-static stlmt: &'static str = "stlmt";
-static thrsk: &'static str = "thrsk";
-static wkcti: &'static str = "wkcti";
-static wkna8: &'static str = "wkna8";
-static wknck: &'static str = "wknck";
-static wkovl: &'static str = "wkovl";
-static wkpos: &'static str = "wkpos";
-static wtoeg: &'static str = "wtoeg";
-
-impl VotingRecord {
-    fn new(fields: Vec<String>) -> VotingRecord {
-        VotingRecord {
-            fields: fields,
-        }
-    }
-}
-
-impl MonkRecord {
-    fn new(fields: Vec<String>) -> MonkRecord {
-        MonkRecord {
-            fields: fields,
-        }
-    }
-}
-
-impl ChessRecord {
-    fn new(fields: Vec<String>) -> ChessRecord {
-        ChessRecord {
-            fields: fields,
-        }
-    }
-}
-
-impl tree::Record for VotingRecord {
-    fn get_attribute(&self, attr_name: &str) -> &str {
-        let val = match attr_name {
-            class_name => &self.fields[0],
-            handicapped => &self.fields[1],
-            water_project => &self.fields[2],
-            budget_resolution => &self.fields[3],
-            physician_freeze => &self.fields[4],
-            el_salvador_aid => &self.fields[5],
-            religious_schools => &self.fields[6],
-            anti_sattelite_ban => &self.fields[7],
-            aid_to_contras => &self.fields[8],
-            mx_missile => &self.fields[9],
-            immigration => &self.fields[10],
-            synfuels_cutback => &self.fields[11],
-            education_spending => &self.fields[12],
-            right_to_sue => &self.fields[13],
-            crime => &self.fields[14],
-            duty_free_exports => &self.fields[15],
-            export_south_africa => &self.fields[16],
-            _ => &self.fields[89], // dirty, error throwing hack
-        };
-        val.as_slice()
-    }
-
-    fn get_attribute_names(&self) -> Vec<&str> {
-        vec![handicapped,water_project,budget_resolution,physician_freeze,
-             el_salvador_aid,religious_schools,anti_sattelite_ban,
-             aid_to_contras,mx_missile,immigration,synfuels_cutback,
-             education_spending,right_to_sue,crime,duty_free_exports,
-             export_south_africa]
-    }
-
-    fn get_label(&self) -> &str {
-        self.fields[0].as_slice()
-    }
-}
-
-impl tree::Record for MonkRecord {
-    fn get_attribute(&self, attr_name: &str) -> &str {
-        let val = match attr_name {
-            class_name => &self.fields[1],
-            a1 => &self.fields[2],
-            a2 => &self.fields[3],
-            a3 => &self.fields[4],
-            a4 => &self.fields[5],
-            a5 => &self.fields[6],
-            a6 => &self.fields[7],
-            _ => {
-                println!("{}", attr_name); // print in case of error
-                &self.fields[99] // dirty, error throwing hack
-            },
-        };
-        val.as_slice()
-    }
-
-   fn get_attribute_names(&self) -> Vec<&str> {
-       vec![a1, a2, a3, a4, a5, a6]
-   }
-
-    fn get_label(&self) -> &str {
-        self.fields[1].as_slice()
-    }
-}
-
-impl tree::Record for ChessRecord {
-    fn get_attribute(&self, attr_name: &str) -> &str {
-        let val = match attr_name {
-            bkblk => &self.fields[0],
-            bknwy => &self.fields[1],
-            bkon8 => &self.fields[2],
-            bkona => &self.fields[3],
-            bkspr => &self.fields[4],
-            bkxbq => &self.fields[5],
-            bkxcr => &self.fields[6],
-            bkxwp => &self.fields[7],
-            blxwp => &self.fields[8],
-            bxqsq => &self.fields[9],
-            cntxt => &self.fields[10],
-            dsopp => &self.fields[11],
-            dwipd => &self.fields[12],
-            hdchk => &self.fields[13],
-            katri => &self.fields[14],
-            mulch => &self.fields[15],
-            qxmsq => &self.fields[16],
-            r2ar8 => &self.fields[17],
-            reskd => &self.fields[18],
-            reskr => &self.fields[19],
-            rimmx => &self.fields[20],
-            rkxwp => &self.fields[21],
-            rxmsq => &self.fields[22],
-            simpl => &self.fields[23],
-            skach => &self.fields[24],
-            skewr => &self.fields[25],
-            skrxp => &self.fields[26],
-            spcop => &self.fields[27],
-            stlmt => &self.fields[28],
-            thrsk => &self.fields[29],
-            wkcti => &self.fields[30],
-            wkna8 => &self.fields[31],
-            wknck => &self.fields[32],
-            wkovl => &self.fields[33],
-            wkpos => &self.fields[34],
-            wtoeg => &self.fields[35],
-            class_name => &self.fields[36],
-            _ => {
-                println!("{}", attr_name); // print in case of error
-                &self.fields[109] // dirty, error throwing hack
-            },
-        };
-        val.as_slice()
-    }
-
-   fn get_attribute_names(&self) -> Vec<&str> {
-       vec!["bkblk","bknwy","bkon8","bkona","bkspr","bkxbq","bkxcr","bkxwp","blxwp","bxqsq","cntxt","dsopp","dwipd","hdchk","katri","mulch","qxmsq","r2ar8","reskd","reskr","rimmx","rkxwp","rxmsq","simpl","skach","skewr","skrxp","spcop","stlmt","thrsk","wkcti","wkna8","wknck","wkovl","wkpos","wtoeg"]
-   }
-
-    fn get_label(&self) -> &str {
-        self.fields[36].as_slice()
+// Trains on stem.data and tests on stem.test, if there is one
+#[cfg(not(test))]
+fn run_stem(path: &Path, invalid: InvalidRows) {
+    let stem = Stem::open(path).unwrap();
+    let label = stem.schema().label_attribute_name();
+    let (training, report) = stem.data(invalid).unwrap();
+    print_issues(format!("{}.data", path.display()).as_slice(), &report);
+    let root_vertex = stem.tree_builder().build(&training.iter().collect());
+
+    println!("impurity: {}", root_vertex.measure());
+    println!("depth: {}, leaves: {}", root_vertex.depth(), root_vertex.leaf_count());
+    println!("{}", root_vertex);
+    match stem.test(invalid).unwrap() {
+        Some((cases, report)) => {
+            print_issues(format!("{}.test", path.display()).as_slice(), &report);
+            let correct = cases.iter().filter(|x| tree::test(&root_vertex, *x, label)).count();
+            println!("");
+            println!("correct: {}/{}", correct, cases.len());
+            println!("incorrect: {}/{}", cases.len() - correct, cases.len());
+        },
+        None => {},
     }
 }
 
-impl<'a> tree::Record for &'a VotingRecord {
-    fn get_attribute(&self, attr_name: &str) -> &str {
-        self.get_attribute(attr_name)
-    }
-
-    fn get_attribute_names(&self) -> Vec<&str> {
-        self.get_attribute_names()
-    }
-
-    fn get_label(&self) -> &str {
-        self.get_label()
+// Reports the rows of a file that do not fit its schema
+#[cfg(not(test))]
+fn print_issues(source: &str, report: &ValidationReport) {
+    for issue in report.issues.iter() {
+        println!("{}: {}", source, issue);
     }
 }
 
-impl<'a> tree::Record for &'a MonkRecord {
-    fn get_attribute(&self, attr_name: &str) -> &str {
-        self.get_attribute(attr_name)
-    }
-
-    fn get_attribute_names(&self) -> Vec<&str> {
-        self.get_attribute_names()
-    }
-
-    fn get_label(&self) -> &str {
-        self.get_label()
-    }
-
+// Reads a data file, reporting the rows that do not fit the schema
+#[cfg(not(test))]
+fn read_valid<'a>(schema: &'a Schema, path: &Path, invalid: InvalidRows) -> Vec<TableRecord<'a>> {
+    let (records, report) = schema.read_file_valid(path, invalid).unwrap();
+    print_issues(path.display().to_string().as_slice(), &report);
+    records
 }
 
-impl<'a> tree::Record for &'a ChessRecord {
-    fn get_attribute(&self, attr_name: &str) -> &str {
-        self.get_attribute(attr_name)
-    }
-
-    fn get_attribute_names(&self) -> Vec<&str> {
-        self.get_attribute_names()
-    }
-
-    fn get_label(&self) -> &str {
-        self.get_label()
-    }
+// Trains on every row of an ARFF file that fits its declared values
+#[cfg(not(test))]
+fn run_arff(path: &Path, invalid: InvalidRows) {
+    let arff = Arff::read_file(path).unwrap();
+    let (records, report) = arff.valid_records(invalid).unwrap();
+    print_issues(path.display().to_string().as_slice(), &report);
+    let root_vertex = arff.tree_builder().build(&records.iter().collect());
+
+    println!("{}", arff.relation());
+    println!("impurity: {}", root_vertex.measure());
+    println!("depth: {}, leaves: {}", root_vertex.depth(), root_vertex.leaf_count());
+    println!("{}", root_vertex);
 }
 
 #[cfg(not(test))]
 fn main() {
+    // An ARFF file or a C4.5 file stem replaces the bundled datasets
+    let args = os::args();
+    let flag = |name: &str| args.iter().skip(1).any(|x| x.as_slice() == name);
+    let export = flag("--arff");
+    // Rows that do not fit their schema are left out and reported, or with --reject end the run
+    let invalid = if flag("--reject") { RejectInvalid } else { DropInvalid };
+    match args.iter().skip(1).find(|x| !x.as_slice().starts_with("--")) {
+        Some(input) if !export => {
+            if input.as_slice().ends_with(".arff") {
+                run_arff(&Path::new(input.as_slice()), invalid);
+            } else {
+                run_stem(&Path::new(input.as_slice()), invalid);
+            }
+            return;
+        },
+        _ => {},
+    }
+
     let fp_voting = &Path::new("./data/voting-records/house-votes-84.data");
     let fp_monk = &Path::new("./data/monks-problems/monks-1.train");
     let fp_chess = &Path::new("./data/king-rook-vs-king-pawn/kr-vs-kp.data");
 
-    let mut rdr_voting = csv::Reader::from_file(fp_voting);
-    let mut rdr_monk = csv::Reader::from_file(fp_monk).delimiter(SPACE);
-    let mut rdr_chess = csv::Reader::from_file(fp_chess);
-
-    let rows_voting = csv::collect(rdr_voting.records()).unwrap();
-    let rows_monk = csv::collect(rdr_monk.records()).unwrap();
-    let rows_chess = csv::collect(rdr_chess.records()).unwrap();
-
-    let mut records_voting = rows_voting.map_in_place(|x| VotingRecord::new(x));
-    let mut records_monk = rows_monk.map_in_place(|x| MonkRecord::new(x));
-    let mut records_chess = rows_chess.map_in_place(|x| ChessRecord::new(x));
-
-    let attr_names_voting = vec![handicapped,water_project,budget_resolution,
-                          physician_freeze,el_salvador_aid,religious_schools,
-                          anti_sattelite_ban,aid_to_contras,mx_missile,
-                          immigration,synfuels_cutback,education_spending,
-                          right_to_sue,crime,duty_free_exports,
-                          export_south_africa];
-    let attr_names_monk = vec![a1, a2, a3, a4, a5, a6];
-    let attr_names_chess = vec![bkblk,bknwy,bkon8,bkona,bkspr,bkxbq,bkxcr,bkxwp,blxwp,bxqsq,cntxt,dsopp,dwipd,hdchk,katri,mulch,qxmsq,r2ar8,reskd,reskr,rimmx,rkxwp,rxmsq,simpl,skach,skewr,skrxp,spcop,stlmt,thrsk,wkcti,wkna8,wknck,wkovl,wkpos,wtoeg];
+    // Column names, domains and markers come from each dataset's description
+    let schema_voting = Names::read_file(&Path::new("./data/voting-records/house-votes-84.names")).unwrap().schema();
+    // Monk's problems rows are space-padded and end with an identifier
+    let schema_monk = Names::read_file(&Path::new("./data/monks-problems/monks.names")).unwrap().schema()
+        .delimiter(' ')
+        .ignore(vec!["Id"]);
+    let schema_chess = Names::read_file(&Path::new("./data/king-rook-vs-king-pawn/kr-vs-kp.names")).unwrap().schema();
+
+    let mut records_voting = read_valid(&schema_voting, fp_voting, invalid);
+    let mut records_monk = read_valid(&schema_monk, fp_monk, invalid);
+    let mut records_chess = read_valid(&schema_chess, fp_chess, invalid);
+
+    // Writes the bundled datasets next to their data files as ARFF
+    if export {
+        let mut file = File::create(&Path::new("./data/voting-records/house-votes-84.arff"));
+        arff::write(&mut file, "house-votes-84", &schema_voting, vec![], records_voting.as_slice()).unwrap();
+        let mut file = File::create(&Path::new("./data/monks-problems/monks-1.arff"));
+        arff::write(&mut file, "monks-1", &schema_monk, vec![], records_monk.as_slice()).unwrap();
+        let mut file = File::create(&Path::new("./data/king-rook-vs-king-pawn/kr-vs-kp.arff"));
+        arff::write(&mut file, "kr-vs-kp", &schema_chess, vec![], records_chess.as_slice()).unwrap();
+        return;
+    }
 
     let mut rng = task_rng();
     rng.shuffle(records_voting.as_mut_slice());
@@ -308,13 +119,17 @@ fn main() {
     let (test_slice_monk, train_slice_monk) = records_monk.split_at_mut(30);
     let (test_slice_chess, train_slice_chess) = records_chess.split_at_mut(30);
 
-    let root_vertex_voting = id3::id3(train_slice_voting.to_vec().iter().collect(), class_name, attr_names_voting, 0f64);
-    let root_vertex_monk = id3::id3(train_slice_monk.to_vec().iter().collect(), class_name, attr_names_monk, 0f64);
-    let root_vertex_chess = id3::id3(train_slice_chess.to_vec().iter().collect(), class_name, attr_names_chess, 0f64);
+    let root_vertex_voting = id3::TreeBuilder::new(schema_voting.label_attribute_name(), schema_voting.attribute_names()).build(&train_slice_voting.iter().collect());
+    let root_vertex_monk = id3::TreeBuilder::new(schema_monk.label_attribute_name(), schema_monk.attribute_names()).build(&train_slice_monk.iter().collect());
+    // Without limits the chess tree grows hundreds of vertices
+    let root_vertex_chess = id3::TreeBuilder::new(schema_chess.label_attribute_name(), schema_chess.attribute_names())
+        .max_depth(10)
+        .min_samples_leaf(5)
+        .build(&train_slice_chess.iter().collect());
 
-    let test_values_voting: Vec<bool> = test_slice_voting.iter().map(|x| tree::test(&root_vertex_voting, x, class_name)).collect();
-    let test_values_monk: Vec<bool> = test_slice_monk.iter().map(|x| tree::test(&root_vertex_monk, x, class_name)).collect();
-    let test_values_chess: Vec<bool> = test_slice_chess.iter().map(|x| tree::test(&root_vertex_chess, x, class_name)).collect();
+    let test_values_voting: Vec<bool> = test_slice_voting.iter().map(|x| tree::test(&root_vertex_voting, x, schema_voting.label_attribute_name())).collect();
+    let test_values_monk: Vec<bool> = test_slice_monk.iter().map(|x| tree::test(&root_vertex_monk, x, schema_monk.label_attribute_name())).collect();
+    let test_values_chess: Vec<bool> = test_slice_chess.iter().map(|x| tree::test(&root_vertex_chess, x, schema_chess.label_attribute_name())).collect();
 
     let total_count_voting = test_values_voting.len();
     let total_count_monk = test_values_monk.len();
@@ -327,6 +142,7 @@ fn main() {
     let false_count_chess = total_count_chess - true_count_chess;
 
     println!("Voting Records");
+    println!("impurity: {}", root_vertex_voting.measure());
     println!("{}", root_vertex_voting);
     println!("");
     println!("correct: {}/30", true_count_voting);
@@ -335,6 +151,7 @@ fn main() {
     println!("\n");
 
     println!("Monks Problems I");
+    println!("impurity: {}", root_vertex_monk.measure());
     println!("{}", root_vertex_monk);
     println!("");
     println!("correct: {}/30", true_count_monk);
@@ -343,6 +160,8 @@ fn main() {
     println!("\n");
 
     println!("King Rook vs King Pawn");
+    println!("impurity: {}", root_vertex_chess.measure());
+    println!("depth: {}, leaves: {}", root_vertex_chess.depth(), root_vertex_chess.leaf_count());
     println!("{}", root_vertex_chess);
     println!("");
     println!("correct: {}/30", true_count_chess);